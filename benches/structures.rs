@@ -0,0 +1,339 @@
+// Benchmarks every structure on the operations its trait offers, at several sizes and with keys
+// in random, sorted, reverse and duplicate-heavy order. Each benchmark runs twice: once measuring
+// wall-clock time, and once counting heap allocations, which shows the cost of path copying (a
+// `Ptr::new` per copied node) independently of the speed of the allocator.
+//
+//     cargo bench --bench structures                 # everything
+//     cargo bench --bench structures -- "heap merge"  # a single group
+
+#[macro_use]
+extern crate criterion;
+extern crate okasaki;
+
+use std::time::Duration;
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{black_box, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use okasaki::heap::{BinomialHeap, Heap, LeftistHeap, PairingHeap, SkewBinomialHeap, SplayHeap};
+use okasaki::map::Map;
+use okasaki::random_access_list::RandomAccessList;
+use okasaki::red_black_tree::RedBlackTree;
+use okasaki::set::Set;
+use okasaki::stack::{List, Stack};
+use okasaki::tree::Tree;
+use okasaki::trie::{ByteTrie, PatriciaTrie};
+
+// the allocator that counts allocations, shared with the allocation tests
+#[path = "../tests/counting/mod.rs"]
+mod counting;
+
+// a criterion measurement that reports allocations instead of time
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        counting::allocations()
+    }
+
+    fn end(&self, start: usize) -> usize {
+        counting::allocations() - start
+    }
+
+    fn add(&self, v1: &usize, v2: &usize) -> usize {
+        v1 + v2
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, v: &usize) -> f64 {
+        *v as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationFormatter
+    }
+}
+
+struct AllocationFormatter;
+
+impl ValueFormatter for AllocationFormatter {
+    fn scale_values(&self, _: f64, _: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(&self, _: f64, throughput: &Throughput, values: &mut [f64]) -> &'static str {
+        match *throughput {
+            Throughput::Elements(n) => {
+                for v in values {
+                    *v /= n as f64;
+                }
+
+                "allocs/elem"
+            },
+            _ => "allocs"
+        }
+    }
+
+    fn scale_for_machines(&self, _: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+const SIZES: [usize; 3] = [100, 1000, 10000];
+
+#[derive(Clone, Copy)]
+enum Distribution {
+    Random,
+    Sorted,
+    Reverse,
+    Duplicates,
+}
+
+use Distribution::{Duplicates, Random, Reverse, Sorted};
+
+const DISTRIBUTIONS: [Distribution; 4] = [Random, Sorted, Reverse, Duplicates];
+
+impl Distribution {
+    fn name(&self) -> &'static str {
+        match *self {
+            Random => "random",
+            Sorted => "sorted",
+            Reverse => "reverse",
+            Duplicates => "duplicates"
+        }
+    }
+
+    // `n` keys, drawn from only 16 distinct values in the duplicate-heavy case
+    fn keys(&self, n: usize) -> Vec<u32> {
+        let mut seed: u32 = 88172645;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+
+        match *self {
+            Random => (0..n).map(|_| next()).collect(),
+            Sorted => (0..n as u32).collect(),
+            Reverse => (0..n as u32).rev().collect(),
+            Duplicates => (0..n).map(|_| next() % 16).collect()
+        }
+    }
+
+    // zero-padded so that the order of the strings is the order of the numbers
+    fn string_keys(&self, n: usize) -> Vec<String> {
+        self.keys(n).into_iter().map(|k| format!("{:010}", k)).collect()
+    }
+}
+
+fn id(structure: &str, d: Distribution, n: usize) -> BenchmarkId {
+    BenchmarkId::new(format!("{}/{}", structure, d.name()), n)
+}
+
+// `label` tells the groups of the time and allocation runs apart, so that criterion doesn't
+// compare the ones of a run against the baseline of the other
+fn bench_heap<H: Heap<u32> + Clone, M: Measurement>(c: &mut Criterion<M>, label: &str, structure: &str) {
+    let mut insert = c.benchmark_group(format!("heap insert{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.keys(n);
+            insert.throughput(Throughput::Elements(n as u64));
+            insert.bench_with_input(id(structure, d, n), &keys, |b, keys| {
+                b.iter(|| keys.iter().fold(H::empty(), |h, k| h.insert(*k)))
+            });
+        }
+    }
+
+    insert.finish();
+
+    let mut delete_min = c.benchmark_group(format!("heap delete_min{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let h = d.keys(n).into_iter().fold(H::empty(), |h, k| h.insert(k));
+            delete_min.throughput(Throughput::Elements(n as u64));
+            delete_min.bench_with_input(id(structure, d, n), &h, |b, h| {
+                b.iter(|| {
+                    let mut h = h.clone();
+
+                    while let Ok(h2) = h.try_delete_min() {
+                        h = h2;
+                    }
+
+                    h
+                })
+            });
+        }
+    }
+
+    delete_min.finish();
+
+    let mut merge = c.benchmark_group(format!("heap merge{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.keys(2 * n);
+            let h1 = keys[..n].iter().fold(H::empty(), |h, k| h.insert(*k));
+            let h2 = keys[n..].iter().fold(H::empty(), |h, k| h.insert(*k));
+            merge.bench_with_input(id(structure, d, n), &(h1, h2), |b, hs| {
+                b.iter(|| hs.0.merge(&hs.1))
+            });
+        }
+    }
+
+    merge.finish();
+}
+
+fn bench_set<S: Set<u32> + Clone, M: Measurement>(c: &mut Criterion<M>, label: &str, structure: &str) {
+    let mut insert = c.benchmark_group(format!("set insert{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.keys(n);
+            insert.throughput(Throughput::Elements(n as u64));
+            insert.bench_with_input(id(structure, d, n), &keys, |b, keys| {
+                b.iter(|| keys.iter().fold(S::empty(), |s, k| s.insert(*k)))
+            });
+        }
+    }
+
+    insert.finish();
+
+    let mut member = c.benchmark_group(format!("set member{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.keys(n);
+            let s = keys.iter().fold(S::empty(), |s, k| s.insert(*k));
+            member.throughput(Throughput::Elements(n as u64));
+            member.bench_with_input(id(structure, d, n), &(s, keys), |b, input| {
+                b.iter(|| input.1.iter().filter(|k| input.0.member(**k)).count())
+            });
+        }
+    }
+
+    member.finish();
+}
+
+fn bench_map<T: Map<String, u32> + Clone, M: Measurement>(c: &mut Criterion<M>, label: &str, structure: &str) {
+    let mut bind = c.benchmark_group(format!("map bind{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.string_keys(n);
+            bind.throughput(Throughput::Elements(n as u64));
+            bind.bench_with_input(id(structure, d, n), &keys, |b, keys| {
+                b.iter_batched(|| keys.clone(), |keys| {
+                    keys.into_iter().enumerate().fold(T::empty(), |m, (i, k)| m.bind(k, i as u32))
+                }, BatchSize::SmallInput)
+            });
+        }
+    }
+
+    bind.finish();
+
+    let mut get = c.benchmark_group(format!("map get{}", label));
+
+    for &d in &DISTRIBUTIONS {
+        for &n in &SIZES {
+            let keys = d.string_keys(n);
+            let m = keys.iter().enumerate().fold(T::empty(), |m, (i, k)| m.bind(k.clone(), i as u32));
+            get.throughput(Throughput::Elements(n as u64));
+            get.bench_with_input(id(structure, d, n), &(m, keys), |b, input| {
+                b.iter_batched(|| input.1.clone(), |keys| {
+                    keys.into_iter().filter_map(|k| input.0.get(k)).count()
+                }, BatchSize::SmallInput)
+            });
+        }
+    }
+
+    get.finish();
+}
+
+// the order of the elements doesn't matter to a stack, so only the size changes
+fn bench_stack<S: Stack<u32> + Clone, M: Measurement>(c: &mut Criterion<M>, label: &str, structure: &str) {
+    let mut cons_tail = c.benchmark_group(format!("stack cons/tail{}", label));
+
+    for &n in &SIZES {
+        cons_tail.throughput(Throughput::Elements(n as u64));
+        cons_tail.bench_with_input(BenchmarkId::new(structure, n), &n, |b, &n| {
+            b.iter(|| {
+                let mut s = (0..n as u32).fold(S::empty(), |s, x| s.cons(x));
+
+                while let Ok(t) = s.try_tail() {
+                    s = t;
+                }
+
+                s
+            })
+        });
+    }
+
+    cons_tail.finish();
+
+    let mut append = c.benchmark_group(format!("stack append{}", label));
+
+    for &n in &SIZES {
+        let s1 = (0..n as u32).fold(S::empty(), |s, x| s.cons(x));
+        let s2 = s1.clone();
+        append.bench_with_input(BenchmarkId::new(structure, n), &(s1, s2), |b, ss| {
+            b.iter(|| black_box(&ss.0).append(&ss.1))
+        });
+    }
+
+    append.finish();
+}
+
+fn structures<M: Measurement>(c: &mut Criterion<M>, label: &str) {
+    bench_heap::<LeftistHeap<u32>, M>(c, label, "LeftistHeap");
+    bench_heap::<BinomialHeap<u32>, M>(c, label, "BinomialHeap");
+    bench_heap::<SkewBinomialHeap<u32>, M>(c, label, "SkewBinomialHeap");
+    bench_heap::<PairingHeap<u32>, M>(c, label, "PairingHeap");
+    bench_heap::<SplayHeap<u32>, M>(c, label, "SplayHeap");
+
+    bench_set::<Tree<u32>, M>(c, label, "Tree");
+    bench_set::<RedBlackTree<u32>, M>(c, label, "RedBlackTree");
+
+    bench_map::<Tree<(String, u32)>, M>(c, label, "Tree");
+    bench_map::<RedBlackTree<(String, u32)>, M>(c, label, "RedBlackTree");
+    bench_map::<PatriciaTrie<u32>, M>(c, label, "PatriciaTrie");
+    bench_map::<ByteTrie<u32>, M>(c, label, "ByteTrie");
+
+    bench_stack::<List<u32>, M>(c, label, "List");
+    bench_stack::<RandomAccessList<u32>, M>(c, label, "RandomAccessList");
+}
+
+fn time(c: &mut Criterion) {
+    structures(c, "");
+}
+
+fn allocations(c: &mut Criterion<Allocations>) {
+    structures(c, " (allocations)");
+}
+
+// there are a few hundred benchmarks in each run, so they get fewer samples than criterion's default
+fn config<M: Measurement>(c: Criterion<M>) -> Criterion<M> {
+    c.sample_size(20).warm_up_time(Duration::from_millis(500)).measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = time_benches;
+    config = config(Criterion::default());
+    targets = time
+}
+
+criterion_group! {
+    name = allocation_benches;
+    config = config(Criterion::default().with_measurement(Allocations));
+    targets = allocations
+}
+
+criterion_main!(time_benches, allocation_benches);
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use tree_layout::LayoutTree;
+
+// A structure that can be exported to Graphviz. A version starts from one node, or from several
+// for a forest such as `BinomialHeap`, and the rest of the graph comes from `LayoutTree`.
+pub trait Dot {
+    type Node: LayoutTree;
+
+    fn dot_roots(&self) -> Vec<&Self::Node>;
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Writes several versions of a structure as a Graphviz digraph in which every node is drawn once,
+// however many versions share it, so that the sharing left by path copying shows as a DAG, like in
+// Figures 2.1 and 2.5. Nodes are told apart by their address, which is that of their `Ptr`
+// allocation below the roots. Each version is a named box pointing to its roots.
+pub fn write_dot<D: Dot, W: Write>(versions: &[(&str, &D)], w: &mut W) -> io::Result<()>
+    where <D::Node as LayoutTree>::Label: Display {
+    let mut ids: HashMap<*const D::Node, usize> = HashMap::new();
+    let mut stack: Vec<&D::Node> = vec![];
+
+    try!(writeln!(w, "digraph {{"));
+    try!(writeln!(w, "    node [shape=circle];"));
+
+    for (i, &(name, d)) in versions.iter().enumerate() {
+        try!(writeln!(w, "    v{} [label=\"{}\", shape=box];", i, escape(name)));
+
+        for n in d.dot_roots() {
+            if n.node().is_none() {
+                continue;
+            }
+
+            if !ids.contains_key(&(n as *const D::Node)) {
+                let id = ids.len();
+                ids.insert(n, id);
+                stack.push(n);
+            }
+
+            try!(writeln!(w, "    v{} -> n{};", i, ids[&(n as *const D::Node)]));
+        }
+
+        // a node is pushed when it gets its id, so its edges are written only once
+        while let Some(n) = stack.pop() {
+            let id = ids[&(n as *const D::Node)];
+
+            if let Some((label, children)) = n.node() {
+                try!(writeln!(w, "    n{} [label=\"{}\"];", id, escape(&format!("{}", label))));
+
+                for c in children {
+                    if c.node().is_none() {
+                        continue;
+                    }
+
+                    if !ids.contains_key(&(c as *const D::Node)) {
+                        let cid = ids.len();
+                        ids.insert(c, cid);
+                        stack.push(c);
+                    }
+
+                    try!(writeln!(w, "    n{} -> n{};", id, ids[&(c as *const D::Node)]));
+                }
+            }
+        }
+    }
+
+    writeln!(w, "}}")
+}
+
+pub fn to_dot<D: Dot>(versions: &[(&str, &D)]) -> String
+    where <D::Node as LayoutTree>::Label: Display {
+    let mut buf = vec![];
+    write_dot(versions, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[cfg(test)]
+fn count_nodes(dot: &str) -> usize {
+    dot.lines().filter(|l| l.starts_with("    n") && l.contains("[label=")).count()
+}
+
+#[test]
+fn dot_lists() {
+    use stack::{List, Stack};
+
+    // Figure 2.1: `zs` copies the cells of `xs` and shares the tail of `ys`. The head cell of a
+    // version isn't behind a `Ptr`, so `ys`'s one is copied too when it gets consed onto.
+    let xs: List<usize> = vec![0, 1, 2].into_iter().collect();
+    let ys: List<usize> = vec![3, 4, 5].into_iter().collect();
+    let zs = xs.append(&ys);
+
+    let dot = to_dot(&[("xs", &xs), ("ys", &ys), ("zs", &zs)]);
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert_eq!(count_nodes(&dot), 10);
+    assert_eq!(dot.matches("[label=\"4\"]").count(), 1);
+    assert_eq!(dot.matches("[label=\"3\"]").count(), 2);
+
+    assert_eq!(count_nodes(&to_dot(&[("xs", &xs), ("xs again", &xs)])), 3);
+}
+
+#[test]
+fn dot_trees() {
+    use heap::{BinomialHeap, Heap, LeftistHeap};
+    use map::Map;
+    use set::Set;
+    use tree::Tree;
+    use trie::PatriciaTrie;
+
+    // Figure 2.5: inserting "e" copies the search path "d", "g", "f" and shares the rest
+    let t: Tree<&str> = Set::empty();
+    let xs = vec!["d", "b", "g", "a", "c", "f", "h"].into_iter().fold(t, |t, x| t.insert(x));
+    let ys = xs.insert("e");
+
+    let dot = to_dot(&[("xs", &xs), ("ys", &ys)]);
+    assert_eq!(count_nodes(&dot), 11);
+    assert_eq!(dot.matches("[label=\"b\"]").count(), 1);
+    assert_eq!(dot.matches("[label=\"g\"]").count(), 2);
+
+    let h1: LeftistHeap<usize> = (0..10).collect();
+    let h2 = h1.insert(10);
+    assert!(count_nodes(&to_dot(&[("h1", &h1), ("h2", &h2)])) < 20);
+
+    let b1: BinomialHeap<usize> = (0..8).collect();
+    let b2 = b1.insert(8);
+    let dot = to_dot(&[("b1", &b1), ("b2", &b2)]);
+    assert_eq!(count_nodes(&dot), 9);
+    assert!(dot.contains("v1 -> n8;"));
+
+    let t1: PatriciaTrie<usize> = vec![("tea", 0), ("ten", 1), ("to", 2)].into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    // a new root above "inn" and a copy of the old one, which shares all of its children
+    let t2 = t1.bind("inn".to_string(), 3);
+    let dot = to_dot(&[("t1", &t1), ("t2", &t2)]);
+    assert_eq!(count_nodes(&dot), 5 + 3);
+    assert!(dot.contains("[label=\"a => (0)\"]"));
+}
@@ -0,0 +1,15 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+// returned by the fallible accessors of structures that can be empty
+// (e.g. `Stack::try_head`, `Heap::try_find_min`)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EmptyError;
+
+impl Display for EmptyError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "empty structure")
+    }
+}
+
+impl Error for EmptyError {}
@@ -1,6 +1,17 @@
+use std::cmp;
 use std::collections::VecDeque;
 use std::fmt::{Display, Error, Formatter};
-use std::rc::Rc;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem;
+
+use dot::Dot;
+use error::EmptyError;
+use ptr::Ptr;
+use stack::List::{Cons, Nil};
+use stack::{List, Stack};
+use tree::Tree;
+use tree_layout::LayoutTree;
 
 pub trait Heap<T: Ord> {
     fn empty() -> Self;
@@ -9,14 +20,55 @@ pub trait Heap<T: Ord> {
     fn merge(&self, &Self) -> Self;
     fn insert(&self, T) -> Self;
 
-    fn find_min(&self) -> T;
-    fn delete_min(&self) -> Self;
+    fn try_find_min(&self) -> Result<T, EmptyError>;
+    fn try_delete_min(&self) -> Result<Self, EmptyError> where Self: Sized;
+
+    fn find_min(&self) -> T {
+        match self.try_find_min() {
+            Ok(x) => x,
+            Err(_) => panic!("empty heap")
+        }
+    }
+
+    fn delete_min(&self) -> Self where Self: Sized {
+        match self.try_delete_min() {
+            Ok(h) => h,
+            Err(_) => panic!("empty heap")
+        }
+    }
+
+    // the minimum together with the rest of the heap, for heaps that find both in one pass
+    fn try_pop_min(&self) -> Result<(T, Self), EmptyError> where Self: Sized {
+        let x = try!(self.try_find_min());
+        let h = try!(self.try_delete_min());
+        Ok((x, h))
+    }
+}
+
+// drains a heap in ascending order
+pub struct IntoIter<T, H> {
+    heap: H,
+    marker: PhantomData<T>,
+}
+
+impl<T: Ord, H: Heap<T>> Iterator for IntoIter<T, H> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self.heap.try_pop_min() {
+            Ok((x, h)) => {
+                self.heap = h;
+                Some(x)
+            },
+            Err(_) => None
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum LeftistHeap<T> {
     Tip,
-    Node(usize, T, Rc<LeftistHeap<T>>, Rc<LeftistHeap<T>>),
+    Node(usize, T, Ptr<LeftistHeap<T>>, Ptr<LeftistHeap<T>>),
 }
 
 use heap::LeftistHeap::{Tip, Node};
@@ -41,7 +93,7 @@ impl<T: Ord + Clone> Heap<T> for LeftistHeap<T> {
             }
         }
 
-        fn make_node<T: Clone>(x: T, l: Rc<LeftistHeap<T>>, r: Rc<LeftistHeap<T>>) -> LeftistHeap<T> {
+        fn make_node<T: Clone>(x: T, l: Ptr<LeftistHeap<T>>, r: Ptr<LeftistHeap<T>>) -> LeftistHeap<T> {
             if rank(&l) >= rank(&r) { Node(rank(&r) + 1, x.clone(), l.clone(), r.clone()) }
             else { Node(rank(&l) + 1, x.clone(), r.clone(), l.clone()) }
         }
@@ -51,70 +103,137 @@ impl<T: Ord + Clone> Heap<T> for LeftistHeap<T> {
             (&Tip, e) => e.clone(),
             (&Node(_, ref x, ref l1, ref r1), &Node(_, ref y, ref l2, ref r2)) => {
                 if *x <= *y {
-                    make_node(x.clone(), l1.clone(), Rc::new(r1.merge(h)))
+                    make_node(x.clone(), l1.clone(), Ptr::new(r1.merge(h)))
                 } else {
-                    make_node(y.clone(), l2.clone(), Rc::new(self.merge(r2)))
+                    make_node(y.clone(), l2.clone(), Ptr::new(self.merge(r2)))
                 }
             }
         }
     }
 
     fn insert(&self, x: T) -> LeftistHeap<T> {
-        let h = Node(1, x, Rc::new(Tip), Rc::new(Tip));
+        let h = Node(1, x, Ptr::new(Tip), Ptr::new(Tip));
         h.merge(self)
     }
 
-    fn find_min(&self) -> T {
+    fn try_find_min(&self) -> Result<T, EmptyError> {
         match *self {
-            Tip => panic!("empty heap"),
-            Node(_, ref x, _, _) => x.clone()
+            Tip => Err(EmptyError),
+            Node(_, ref x, _, _) => Ok(x.clone())
         }
     }
 
-    fn delete_min(&self) -> LeftistHeap<T> {
+    fn try_delete_min(&self) -> Result<LeftistHeap<T>, EmptyError> {
         match *self {
-            Tip => panic!("empty heap"),
-            Node(_, _, ref l, ref r) => l.merge(r)
+            Tip => Err(EmptyError),
+            Node(_, _, ref l, ref r) => Ok(l.merge(r))
+        }
+    }
+}
+
+// inserting elements in decreasing order builds a heap whose left spine holds every element, so
+// like `Tree` it is dropped from an explicit stack instead of recursively
+impl<T> Drop for LeftistHeap<T> {
+    fn drop(&mut self) {
+        fn take_children<T>(h: &mut LeftistHeap<T>, stack: &mut Vec<LeftistHeap<T>>) {
+            if let Node(_, _, ref mut l, ref mut r) = *h {
+                if let Some(l) = Ptr::get_mut(l) { stack.push(mem::replace(l, Tip)); }
+                if let Some(r) = Ptr::get_mut(r) { stack.push(mem::replace(r, Tip)); }
+            }
+        }
+
+        let mut stack = vec![];
+        take_children(self, &mut stack);
+
+        while let Some(mut h) = stack.pop() {
+            take_children(&mut h, &mut stack);
         }
     }
 }
 
 impl<T: Display> Display for LeftistHeap<T> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        fn aux<T: Display>(f: &mut Formatter, t: &LeftistHeap<T>, right: bool, indent: &str) -> Result<(), Error> {
-            match *t {
-                Node(ref rank, ref x, ref l, ref r) => {
-                    try!(aux(f, r, true, &(indent.to_string() + if right { "        " } else { " |      " })));
+        // same layout as `Tree`, see its `Display` implementation
+        enum Frame<'a, T: 'a> {
+            Visit(&'a LeftistHeap<T>, bool, String),
+            Line(String, usize, &'a T),
+        }
 
-                    try!(write!(f, "{}", indent));
-                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
-                    try!(write!(f, "{}", "----- "));
+        fn arrow(right: bool) -> &'static str {
+            if right { " /----- " } else { " \\----- " }
+        }
 
-                    try!(writeln!(f, "(#{}, {})", rank, x));
+        let mut stack = match *self {
+            Node(rank, ref x, ref l, ref r) =>
+                vec![Frame::Visit(l, false, "".to_string()),
+                     Frame::Line("".to_string(), rank, x),
+                     Frame::Visit(r, true, "".to_string())],
+            Tip => vec![]
+        };
 
-                    aux(f, l, false, &(indent.to_string() + if right { " |      " } else { "        " }))
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Visit(&Node(rank, ref x, ref l, ref r), right, indent) => {
+                    stack.push(Frame::Visit(l, false, indent.clone() + if right { " |      " } else { "        " }));
+                    stack.push(Frame::Line(indent.clone() + arrow(right), rank, x));
+                    stack.push(Frame::Visit(r, true, indent + if right { "        " } else { " |      " }));
                 },
-                Tip => {
-                    try!(write!(f, "{}", indent));
-                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
-                    try!(write!(f, "{}", "----- "));
-
-                    writeln!(f, "{}", "()")
-                }
+                Frame::Visit(&Tip, right, indent) =>
+                    try!(writeln!(f, "{}{}()", indent, arrow(right))),
+                Frame::Line(prefix, rank, x) =>
+                    try!(writeln!(f, "{}(#{}, {})", prefix, rank, x))
             }
         }
 
+        Result::Ok(())
+    }
+}
+
+// the ranks are left out of the labels
+impl<T: Clone> LayoutTree for LeftistHeap<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&LeftistHeap<T>>)> {
         match *self {
-            Node(ref rank, ref x, ref l, ref r) => {
-                try!(aux(f, r, true, ""));
-                try!(writeln!(f, "(#{}, {})", rank, x));
-                aux(f, l, false, "")
-            },
-            Tip => Result::Ok(())
+            Node(_, ref x, ref l, ref r) => Some((x.clone(), vec![&**l, &**r])),
+            Tip => None
         }
     }
 }
 
+impl<T: Clone> Dot for LeftistHeap<T> {
+    type Node = LeftistHeap<T>;
+
+    fn dot_roots(&self) -> Vec<&LeftistHeap<T>> {
+        vec![self]
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for LeftistHeap<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> LeftistHeap<T> {
+        let mut h = LeftistHeap::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for LeftistHeap<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntoIterator for LeftistHeap<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, LeftistHeap<T>>;
+
+    fn into_iter(self) -> IntoIter<T, LeftistHeap<T>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
 macro_rules! vecdeque {
     ($( $v: expr ),*) => {{
          let mut vec = ::std::collections::VecDeque::new();
@@ -123,7 +242,10 @@ macro_rules! vecdeque {
     }}
 }
 
-pub type BinomialHeap<T> = VecDeque<Rc<BinomialTree<T>>>;
+// a newtype rather than an alias for the `VecDeque` since 0.0.2, so that the std and serde traits
+// can be implemented for it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BinomialHeap<T>(VecDeque<Ptr<BinomialTree<T>>>);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BinomialTree<T>(usize, T, BinomialHeap<T>);
@@ -134,11 +256,11 @@ fn link<T: Clone + Ord>(t1: &BinomialTree<T>, t2: &BinomialTree<T>) -> BinomialT
 
     if x1 <= x2 {
         let mut c = c1.clone();
-        c.push_front(Rc::new(t2.clone()));
+        c.0.push_front(Ptr::new(t2.clone()));
         BinomialTree(r + 1, x1.clone(), c)
     } else {
         let mut c = c2.clone();
-        c.push_front(Rc::new(t1.clone()));
+        c.0.push_front(Ptr::new(t1.clone()));
         BinomialTree(r + 1, x2.clone(), c)
     }
 }
@@ -153,71 +275,102 @@ fn root<T: Clone>(t: &BinomialTree<T>) -> T {
     x.clone()
 }
 
+impl<T: Clone> BinomialHeap<T> {
+    // the heap without its first `n` trees
+    fn drop_trees(&self, n: usize) -> BinomialHeap<T> {
+        BinomialHeap(self.0.clone().split_off(n))
+    }
+}
+
+impl<T> BinomialHeap<T> {
+    // the trees of the heap in increasing order of rank, to be laid out one at a time
+    pub fn trees(&self) -> Vec<&BinomialTree<T>> {
+        self.0.iter().map(|t| &**t).collect()
+    }
+}
+
 fn insert_tree<T: Clone + Ord>(h: &BinomialHeap<T>, t: &BinomialTree<T>) -> BinomialHeap<T> {
-    match h.front() {
+    match h.0.front() {
         Some(t2) => {
-            let mut h2 = h.clone();
-
             if rank(t) < rank(t2) {
-                h2.push_front(Rc::new(t.clone()));
+                let mut h2 = h.clone();
+                h2.0.push_front(Ptr::new(t.clone()));
                 h2
             } else {
-                insert_tree(&h2.split_off(1), &link(t, t2))
+                insert_tree(&h.drop_trees(1), &link(t, t2))
             }
         },
-        _ => vecdeque![Rc::new(t.clone())],
+        _ => BinomialHeap(vecdeque![Ptr::new(t.clone())]),
     }
 }
 
-fn remove_min_tree<T: Clone + Ord>(h: &BinomialHeap<T>) -> (BinomialTree<T>, BinomialHeap<T>) {
-    match h.len() {
-        0 => panic!("remove tree from empty heap"),
+fn remove_min_tree<T: Clone + Ord>(h: &BinomialHeap<T>) -> Result<(BinomialTree<T>, BinomialHeap<T>), EmptyError> {
+    match h.0.len() {
+        0 => Err(EmptyError),
         1 => {
-            ((**h.front().unwrap()).clone(), vecdeque![])
+            Ok(((**h.0.front().unwrap()).clone(), BinomialHeap(vecdeque![])))
         },
         _ => {
-            let t = h.front().unwrap();
-            let ts = h.clone().split_off(1);
+            let t = h.0.front().unwrap();
+            let ts = h.drop_trees(1);
 
-            let (t1, mut ts1) = remove_min_tree(&ts);
+            let (t1, mut ts1) = try!(remove_min_tree(&ts));
 
             if (root(t) < root(&t1)) {
-                ((**t).clone(), ts)
+                Ok(((**t).clone(), ts))
             } else {
-                ts1.push_front(t.clone());
-                (t1, ts1)
+                ts1.0.push_front(t.clone());
+                Ok((t1, ts1))
             }
         }
     }
 }
 
+// a heap is a forest, so its trees are drawn one at a time
+impl<T: Clone> LayoutTree for BinomialTree<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&BinomialTree<T>>)> {
+        let BinomialTree(_, ref x, ref c) = *self;
+        Some((x.clone(), c.0.iter().map(|t| &**t).collect()))
+    }
+}
+
+impl<T: Clone> Dot for BinomialHeap<T> {
+    type Node = BinomialTree<T>;
+
+    fn dot_roots(&self) -> Vec<&BinomialTree<T>> {
+        self.trees()
+    }
+}
+
 impl<T: Ord + Clone> Heap<T> for BinomialHeap<T> {
     fn empty() -> BinomialHeap<T> {
-        vecdeque![]
+        BinomialHeap(vecdeque![])
     }
 
     fn is_empty(&self) -> bool {
-        self.is_empty()
+        self.0.is_empty()
     }
 
     fn merge(&self, h: &BinomialHeap<T>) -> BinomialHeap<T> {
-        match (self.front(), h.front()) {
+        match (self.0.front(), h.0.front()) {
             (_, None) => self.clone(),
             (None, _) => h.clone(),
             (Some(t1), Some(t2)) => {
                 if rank(t1) < rank(t2) {
 
-                    let mut h = self.clone().split_off(1).merge(h);
-                    h.push_front(t1.clone());
+                    let mut h = self.drop_trees(1).merge(h);
+                    h.0.push_front(t1.clone());
                     h
                 } else if rank(t2) < rank(t1) {
 
-                    let mut h = self.merge(&h.clone().split_off(1));
-                    h.push_front(t2.clone());
+                    let mut h = self.merge(&h.drop_trees(1));
+                    h.0.push_front(t2.clone());
                     h
                 } else {
                     insert_tree(
-                        &self.clone().split_off(1).merge(&h.clone().split_off(2)),
+                        &self.drop_trees(1).merge(&h.drop_trees(1)),
                         &link(t1, t2))
                 }
             }
@@ -225,18 +378,795 @@ impl<T: Ord + Clone> Heap<T> for BinomialHeap<T> {
     }
 
     fn insert(&self, x: T) -> BinomialHeap<T> {
-        insert_tree(self, &BinomialTree(0, x, vecdeque![]))
+        insert_tree(self, &BinomialTree(0, x, BinomialHeap(vecdeque![])))
     }
 
-    fn find_min(&self) -> T {
-        let (t, _) = remove_min_tree(self);
-        root(&t)
+    fn try_find_min(&self) -> Result<T, EmptyError> {
+        let (t, _) = try!(remove_min_tree(self));
+        Ok(root(&t))
+    }
+
+    fn try_delete_min(&self) -> Result<BinomialHeap<T>, EmptyError> {
+        let (_, h) = try!(self.try_pop_min());
+        Ok(h)
+    }
+
+    fn try_pop_min(&self) -> Result<(T, BinomialHeap<T>), EmptyError> {
+        let (BinomialTree(_, x, ts1), ts2) = try!(remove_min_tree(self));
+        let ts1 = BinomialHeap(ts1.0.into_iter().rev().collect());
+        Ok((x, ts1.merge(&ts2)))
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for BinomialHeap<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> BinomialHeap<T> {
+        let mut h = BinomialHeap::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for BinomialHeap<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
     }
+}
+
+impl<T: Ord + Clone> IntoIterator for BinomialHeap<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, BinomialHeap<T>>;
 
-    fn delete_min(&self) -> BinomialHeap<T> {
-        let (BinomialTree(_, _, ts1), ts2) = remove_min_tree(self);
-        let ts1: BinomialHeap<T> = ts1.into_iter().rev().collect();
-        ts1.merge(&ts2)
+    fn into_iter(self) -> IntoIter<T, BinomialHeap<T>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
+// Section 9.3.2: a skew binomial tree also keeps a list of up to `rank` extra elements next to its
+// root, which lets `insert` link the two smallest trees of equal rank together with the new element
+// in constant time. The trees of a heap are in increasing order of rank, only the first two may have
+// the same rank, and their children are in decreasing order of rank.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkewBinomialTree<T>(usize, T, List<T>, List<Ptr<SkewBinomialTree<T>>>);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkewBinomialHeap<T>(List<Ptr<SkewBinomialTree<T>>>);
+
+fn skew_rank<T>(t: &SkewBinomialTree<T>) -> usize {
+    let SkewBinomialTree(r, _, _, _) = *t;
+    r
+}
+
+fn skew_root<T: Clone>(t: &SkewBinomialTree<T>) -> T {
+    let SkewBinomialTree(_, ref x, _, _) = *t;
+    x.clone()
+}
+
+fn skew_link<T: Clone + Ord>(t1: &Ptr<SkewBinomialTree<T>>, t2: &Ptr<SkewBinomialTree<T>>) -> SkewBinomialTree<T> {
+    let SkewBinomialTree(r, ref x1, ref xs1, ref c1) = **t1;
+    let SkewBinomialTree(_, ref x2, ref xs2, ref c2) = **t2;
+
+    if x1 <= x2 {
+        SkewBinomialTree(r + 1, x1.clone(), xs1.clone(), c1.cons(t2.clone()))
+    } else {
+        SkewBinomialTree(r + 1, x2.clone(), xs2.clone(), c2.cons(t1.clone()))
+    }
+}
+
+// links two trees of the same rank and the element `x` into a tree of the next rank
+fn skew_link_element<T: Clone + Ord>(x: T, t1: &Ptr<SkewBinomialTree<T>>, t2: &Ptr<SkewBinomialTree<T>>) -> SkewBinomialTree<T> {
+    let SkewBinomialTree(r, y, ys, c) = skew_link(t1, t2);
+
+    if x <= y {
+        SkewBinomialTree(r, x, ys.cons(y), c)
+    } else {
+        SkewBinomialTree(r, y, ys.cons(x), c)
+    }
+}
+
+fn skew_insert_tree<T: Clone + Ord>(t: Ptr<SkewBinomialTree<T>>, ts: &List<Ptr<SkewBinomialTree<T>>>) -> List<Ptr<SkewBinomialTree<T>>> {
+    match *ts {
+        Cons(ref t2, ref rest) if skew_rank(&t) >= skew_rank(t2) =>
+            skew_insert_tree(Ptr::new(skew_link(&t, t2)), rest),
+        _ => ts.cons(t)
+    }
+}
+
+fn skew_merge_trees<T: Clone + Ord>(ts1: &List<Ptr<SkewBinomialTree<T>>>, ts2: &List<Ptr<SkewBinomialTree<T>>>) -> List<Ptr<SkewBinomialTree<T>>> {
+    match (ts1, ts2) {
+        (_, &Nil) => ts1.clone(),
+        (&Nil, _) => ts2.clone(),
+        (&Cons(ref t1, ref r1), &Cons(ref t2, ref r2)) =>
+            if skew_rank(t1) < skew_rank(t2) {
+                skew_merge_trees(r1, ts2).cons(t1.clone())
+            } else if skew_rank(t2) < skew_rank(t1) {
+                skew_merge_trees(ts1, r2).cons(t2.clone())
+            } else {
+                skew_insert_tree(Ptr::new(skew_link(t1, t2)), &skew_merge_trees(r1, r2))
+            }
+    }
+}
+
+// removes the only possible duplicate rank, at the front of the list
+fn skew_normalize<T: Clone + Ord>(ts: &List<Ptr<SkewBinomialTree<T>>>) -> List<Ptr<SkewBinomialTree<T>>> {
+    match *ts {
+        Cons(ref t, ref rest) => skew_insert_tree(t.clone(), rest),
+        Nil => Nil
+    }
+}
+
+fn skew_remove_min_tree<T: Clone + Ord>(ts: &List<Ptr<SkewBinomialTree<T>>>) -> Result<(Ptr<SkewBinomialTree<T>>, List<Ptr<SkewBinomialTree<T>>>), EmptyError> {
+    match *ts {
+        Nil => Err(EmptyError),
+        Cons(ref t, ref rest) if rest.is_empty() => Ok((t.clone(), Nil)),
+        Cons(ref t, ref rest) => {
+            let (t1, ts1) = try!(skew_remove_min_tree(rest));
+
+            if skew_root(t) <= skew_root(&t1) {
+                Ok((t.clone(), (**rest).clone()))
+            } else {
+                Ok((t1, ts1.cons(t.clone())))
+            }
+        }
+    }
+}
+
+impl<T> SkewBinomialHeap<T> {
+    // the trees of the heap in increasing order of rank, to be laid out one at a time
+    pub fn trees(&self) -> Vec<&SkewBinomialTree<T>> {
+        self.0.iter().map(|t| &**t).collect()
+    }
+}
+
+// the extra elements next to the root are left out of the labels
+impl<T: Clone> LayoutTree for SkewBinomialTree<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&SkewBinomialTree<T>>)> {
+        let SkewBinomialTree(_, ref x, _, ref c) = *self;
+        Some((x.clone(), c.iter().map(|t| &**t).collect()))
+    }
+}
+
+impl<T: Clone> Dot for SkewBinomialHeap<T> {
+    type Node = SkewBinomialTree<T>;
+
+    fn dot_roots(&self) -> Vec<&SkewBinomialTree<T>> {
+        self.trees()
+    }
+}
+
+impl<T: Ord + Clone> Heap<T> for SkewBinomialHeap<T> {
+    fn empty() -> SkewBinomialHeap<T> {
+        SkewBinomialHeap(Nil)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn merge(&self, h: &SkewBinomialHeap<T>) -> SkewBinomialHeap<T> {
+        SkewBinomialHeap(skew_merge_trees(&skew_normalize(&self.0), &skew_normalize(&h.0)))
+    }
+
+    fn insert(&self, x: T) -> SkewBinomialHeap<T> {
+        if let Cons(ref t1, ref ts) = self.0 {
+            if let Cons(ref t2, ref rest) = **ts {
+                if skew_rank(t1) == skew_rank(t2) {
+                    return SkewBinomialHeap(rest.cons(Ptr::new(skew_link_element(x, t1, t2))));
+                }
+            }
+        }
+
+        SkewBinomialHeap(self.0.cons(Ptr::new(SkewBinomialTree(0, x, Nil, Nil))))
+    }
+
+    fn try_find_min(&self) -> Result<T, EmptyError> {
+        let (t, _) = try!(skew_remove_min_tree(&self.0));
+        Ok(skew_root(&t))
+    }
+
+    fn try_delete_min(&self) -> Result<SkewBinomialHeap<T>, EmptyError> {
+        let (_, h) = try!(self.try_pop_min());
+        Ok(h)
+    }
+
+    fn try_pop_min(&self) -> Result<(T, SkewBinomialHeap<T>), EmptyError> {
+        let (t, ts2) = try!(skew_remove_min_tree(&self.0));
+        let SkewBinomialTree(_, ref x, ref xs, ref ts1) = *t;
+
+        let h = SkewBinomialHeap(skew_merge_trees(&ts1.reverse(), &skew_normalize(&ts2)));
+        Ok((x.clone(), xs.iter().fold(h, |h, x| h.insert(x.clone()))))
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for SkewBinomialHeap<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> SkewBinomialHeap<T> {
+        let mut h = SkewBinomialHeap::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for SkewBinomialHeap<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntoIterator for SkewBinomialHeap<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, SkewBinomialHeap<T>>;
+
+    fn into_iter(self) -> IntoIter<T, SkewBinomialHeap<T>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
+// Section 3.2.1 (Exercise 3.7): wraps any heap and keeps its minimum element next to it, so that
+// `find_min` takes O(1) time while the other operations keep the bounds of the underlying heap
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExplicitMin<T, H> {
+    min: Option<T>,
+    heap: H,
+}
+
+impl<T: Ord + Clone, H: Heap<T>> ExplicitMin<T, H> {
+    pub fn new(h: H) -> ExplicitMin<T, H> {
+        ExplicitMin { min: h.try_find_min().ok(), heap: h }
+    }
+}
+
+impl<T: Ord + Clone, H: Heap<T>> Heap<T> for ExplicitMin<T, H> {
+    fn empty() -> ExplicitMin<T, H> {
+        ExplicitMin { min: None, heap: H::empty() }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min.is_none()
+    }
+
+    fn merge(&self, h: &ExplicitMin<T, H>) -> ExplicitMin<T, H> {
+        let min = match (self.min.clone(), h.min.clone()) {
+            (Some(x), Some(y)) => Some(cmp::min(x, y)),
+            (x, None) => x,
+            (None, y) => y
+        };
+
+        ExplicitMin { min: min, heap: self.heap.merge(&h.heap) }
+    }
+
+    fn insert(&self, x: T) -> ExplicitMin<T, H> {
+        let min = match self.min {
+            Some(ref m) if *m <= x => m.clone(),
+            _ => x.clone()
+        };
+
+        ExplicitMin { min: Some(min), heap: self.heap.insert(x) }
+    }
+
+    fn try_find_min(&self) -> Result<T, EmptyError> {
+        self.min.clone().ok_or(EmptyError)
+    }
+
+    fn try_delete_min(&self) -> Result<ExplicitMin<T, H>, EmptyError> {
+        let h = try!(self.heap.try_delete_min());
+        Ok(ExplicitMin::new(h))
+    }
+}
+
+impl<T: Ord + Clone, H: Heap<T>> FromIterator<T> for ExplicitMin<T, H> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> ExplicitMin<T, H> {
+        let mut h = ExplicitMin::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone, H: Heap<T>> Extend<T> for ExplicitMin<T, H> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
+    }
+}
+
+impl<T: Ord + Clone, H: Heap<T>> IntoIterator for ExplicitMin<T, H> {
+    type Item = T;
+    type IntoIter = IntoIter<T, ExplicitMin<T, H>>;
+
+    fn into_iter(self) -> IntoIter<T, ExplicitMin<T, H>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
+// Section 5.5: a pairing heap is a multiway tree whose root is its minimum. `merge` makes the
+// heap with the larger root the first child of the other one and only `delete_min` does real work,
+// merging the children of the root in pairs from left to right and the pairs from right to left.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PairingHeap<T> {
+    Empty,
+    Tree(T, List<Ptr<PairingHeap<T>>>),
+}
+
+impl<T: Ord + Clone> Heap<T> for PairingHeap<T> {
+    fn empty() -> PairingHeap<T> {
+        PairingHeap::Empty
+    }
+
+    fn is_empty(&self) -> bool {
+        match *self {
+            PairingHeap::Empty => true,
+            _ => false
+        }
+    }
+
+    fn merge(&self, h: &PairingHeap<T>) -> PairingHeap<T> {
+        match (self, h) {
+            (e, &PairingHeap::Empty) => e.clone(),
+            (&PairingHeap::Empty, e) => e.clone(),
+            (&PairingHeap::Tree(ref x, ref hs1), &PairingHeap::Tree(ref y, ref hs2)) =>
+                if *x <= *y {
+                    PairingHeap::Tree(x.clone(), hs1.cons(Ptr::new(h.clone())))
+                } else {
+                    PairingHeap::Tree(y.clone(), hs2.cons(Ptr::new(self.clone())))
+                }
+        }
+    }
+
+    fn insert(&self, x: T) -> PairingHeap<T> {
+        PairingHeap::Tree(x, Nil).merge(self)
+    }
+
+    fn try_find_min(&self) -> Result<T, EmptyError> {
+        match *self {
+            PairingHeap::Empty => Err(EmptyError),
+            PairingHeap::Tree(ref x, _) => Ok(x.clone())
+        }
+    }
+
+    // the root can have a child per element, so the pairs are collected into a vector instead of
+    // recursing once per pair
+    fn try_delete_min(&self) -> Result<PairingHeap<T>, EmptyError> {
+        match *self {
+            PairingHeap::Empty => Err(EmptyError),
+            PairingHeap::Tree(_, ref hs) => {
+                let mut pairs = vec![];
+                let mut it = hs.iter();
+
+                while let Some(h1) = it.next() {
+                    match it.next() {
+                        Some(h2) => pairs.push(h1.merge(h2)),
+                        None => pairs.push((**h1).clone())
+                    }
+                }
+
+                Ok(pairs.into_iter().rev().fold(PairingHeap::Empty, |acc, h| h.merge(&acc)))
+            }
+        }
+    }
+}
+
+// inserting elements in decreasing order nests every heap inside the first child of the next one
+impl<T> Drop for PairingHeap<T> {
+    fn drop(&mut self) {
+        fn take_children<T>(h: &mut PairingHeap<T>, stack: &mut Vec<PairingHeap<T>>) {
+            if let PairingHeap::Tree(_, ref mut hs) = *h {
+                let mut l = mem::replace(hs, Nil);
+
+                loop {
+                    let rest = match l {
+                        Cons(ref mut h, ref mut rest) => {
+                            if let Some(h) = Ptr::get_mut(h) { stack.push(mem::replace(h, PairingHeap::Empty)); }
+
+                            match Ptr::get_mut(rest) {
+                                Some(rest) => mem::replace(rest, Nil),
+                                None => Nil
+                            }
+                        },
+                        Nil => break
+                    };
+
+                    l = rest;
+                }
+            }
+        }
+
+        let mut stack = vec![];
+        take_children(self, &mut stack);
+
+        while let Some(mut h) = stack.pop() {
+            take_children(&mut h, &mut stack);
+        }
+    }
+}
+
+impl<T: Clone> LayoutTree for PairingHeap<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&PairingHeap<T>>)> {
+        match *self {
+            PairingHeap::Tree(ref x, ref hs) => Some((x.clone(), hs.iter().map(|h| &**h).collect())),
+            PairingHeap::Empty => None
+        }
+    }
+}
+
+impl<T: Clone> Dot for PairingHeap<T> {
+    type Node = PairingHeap<T>;
+
+    fn dot_roots(&self) -> Vec<&PairingHeap<T>> {
+        vec![self]
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for PairingHeap<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> PairingHeap<T> {
+        let mut h = PairingHeap::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for PairingHeap<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntoIterator for PairingHeap<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, PairingHeap<T>>;
+
+    fn into_iter(self) -> IntoIter<T, PairingHeap<T>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
+// Section 5.4: a splay heap is a binary search tree that is restructured every time it is
+// partitioned around a pivot, rotating the paths it walks along so that they get shorter
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplayHeap<T>(Tree<T>);
+
+impl<T> SplayHeap<T> {
+    // the search tree of the heap, to be laid out as it has been left by the last splay
+    pub fn tree(&self) -> &Tree<T> {
+        &self.0
+    }
+}
+
+impl<T: Clone> Dot for SplayHeap<T> {
+    type Node = Tree<T>;
+
+    fn dot_roots(&self) -> Vec<&Tree<T>> {
+        vec![self.tree()]
+    }
+}
+
+// splits `t` into the elements smaller than or equal to `pivot` and the ones bigger than it. Every
+// step of the walk down fixes the top of both halves, leaving a hole that the partition of the
+// subtree it goes on with fills: `small` keeps nodes missing their right child and `big` nodes
+// missing their left one, and both halves are rebuilt bottom-up at the end. The walk is iterative
+// because inserting in increasing order makes the left spine as long as the heap.
+fn partition<T: Ord + Clone>(pivot: &T, t: &Tree<T>) -> (Tree<T>, Tree<T>) {
+    let mut small: Vec<(Ptr<Tree<T>>, T)> = vec![];
+    let mut big: Vec<(T, Ptr<Tree<T>>)> = vec![];
+    let mut t = t;
+
+    let (mut s, mut g) = loop {
+        match *t {
+            Tree::Tip => break (Tree::Tip, Tree::Tip),
+            Tree::Node(ref a, ref x, ref b) if *x <= *pivot =>
+                match **b {
+                    Tree::Tip => break (t.clone(), Tree::Tip),
+                    Tree::Node(ref b1, ref y, ref b2) if *y <= *pivot => {
+                        small.push((Ptr::new(Tree::Node(a.clone(), x.clone(), b1.clone())), y.clone()));
+                        t = b2;
+                    },
+                    Tree::Node(ref b1, ref y, ref b2) => {
+                        small.push((a.clone(), x.clone()));
+                        big.push((y.clone(), b2.clone()));
+                        t = b1;
+                    }
+                },
+            Tree::Node(ref a, ref y, ref b) =>
+                match **a {
+                    Tree::Tip => break (Tree::Tip, t.clone()),
+                    Tree::Node(ref a1, ref x, ref a2) if *x <= *pivot => {
+                        small.push((a1.clone(), x.clone()));
+                        big.push((y.clone(), b.clone()));
+                        t = a2;
+                    },
+                    Tree::Node(ref a1, ref x, ref a2) => {
+                        big.push((x.clone(), Ptr::new(Tree::Node(a2.clone(), y.clone(), b.clone()))));
+                        t = a1;
+                    }
+                }
+        }
+    };
+
+    while let Some((a, x)) = small.pop() {
+        s = Tree::Node(a, x, Ptr::new(s));
+    }
+
+    while let Some((x, b)) = big.pop() {
+        g = Tree::Node(Ptr::new(g), x, b);
+    }
+
+    (s, g)
+}
+
+// merges every subtree of `s` with the part of `t` that falls between its neighbours. `Merge`
+// frames are expanded into the merges of both children, and `Join` frames put the results of
+// the last two back together under their element, so that a long spine in `s` doesn't recurse.
+fn merge_splay<T: Ord + Clone>(s: &Tree<T>, t: &Tree<T>) -> Tree<T> {
+    enum Frame<T> {
+        Merge(Tree<T>, Tree<T>),
+        Join(T),
+    }
+
+    let mut stack = vec![Frame::Merge(s.clone(), t.clone())];
+    let mut results: Vec<Tree<T>> = vec![];
+
+    while let Some(frame) = stack.pop() {
+        match frame {
+            Frame::Merge(s, t) =>
+                match s {
+                    Tree::Tip => results.push(t),
+                    Tree::Node(ref a, ref x, ref b) => {
+                        let (ta, tb) = partition(x, &t);
+                        stack.push(Frame::Join(x.clone()));
+                        stack.push(Frame::Merge((**b).clone(), tb));
+                        stack.push(Frame::Merge((**a).clone(), ta));
+                    }
+                },
+            Frame::Join(x) => {
+                let b = results.pop().unwrap();
+                let a = results.pop().unwrap();
+                results.push(Tree::Node(Ptr::new(a), x, Ptr::new(b)));
+            }
+        }
+    }
+
+    results.pop().unwrap()
+}
+
+impl<T: Ord + Clone> Heap<T> for SplayHeap<T> {
+    fn empty() -> SplayHeap<T> {
+        SplayHeap(Tree::Tip)
+    }
+
+    fn is_empty(&self) -> bool {
+        match self.0 {
+            Tree::Tip => true,
+            _ => false
+        }
+    }
+
+    fn merge(&self, h: &SplayHeap<T>) -> SplayHeap<T> {
+        SplayHeap(merge_splay(&self.0, &h.0))
+    }
+
+    fn insert(&self, x: T) -> SplayHeap<T> {
+        let (a, b) = partition(&x, &self.0);
+        SplayHeap(Tree::Node(Ptr::new(a), x, Ptr::new(b)))
+    }
+
+    fn try_find_min(&self) -> Result<T, EmptyError> {
+        let mut t = &self.0;
+
+        loop {
+            match *t {
+                Tree::Tip => return Err(EmptyError),
+                Tree::Node(ref a, ref x, _) =>
+                    match **a {
+                        Tree::Tip => return Ok(x.clone()),
+                        _ => t = a
+                    }
+            }
+        }
+    }
+
+    // rotates every pair of nodes on the left spine, which halves its length; the spine is
+    // walked iteratively because inserting in increasing order makes it as long as the heap
+    fn try_delete_min(&self) -> Result<SplayHeap<T>, EmptyError> {
+        let mut path = vec![];
+        let mut t = &self.0;
+
+        let min = loop {
+            match *t {
+                Tree::Tip => return Err(EmptyError),
+                Tree::Node(ref a, ref y, ref c) =>
+                    match **a {
+                        Tree::Tip => break (**c).clone(),
+                        Tree::Node(ref a1, ref x, ref b) => {
+                            let r = Tree::Node(b.clone(), y.clone(), c.clone());
+
+                            match **a1 {
+                                Tree::Tip => break r,
+                                _ => {
+                                    path.push((x, r));
+                                    t = a1;
+                                }
+                            }
+                        }
+                    }
+            }
+        };
+
+        Ok(SplayHeap(path.into_iter().rev().fold(min, |acc, (x, r)| Tree::Node(Ptr::new(acc), x.clone(), Ptr::new(r)))))
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for SplayHeap<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> SplayHeap<T> {
+        let mut h = SplayHeap::empty();
+        h.extend(iter);
+        h
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for SplayHeap<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.insert(x);
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntoIterator for SplayHeap<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, SplayHeap<T>>;
+
+    fn into_iter(self) -> IntoIter<T, SplayHeap<T>> {
+        IntoIter { heap: self, marker: PhantomData }
+    }
+}
+
+// checks the heap order and that the rank of every node is the length of its right spine, which
+// is never longer than the left one
+#[cfg(test)]
+impl<T: Ord> LeftistHeap<T> {
+    pub(crate) fn check_invariants(&self) -> usize {
+        match *self {
+            Tip => 0,
+            Node(r, ref x, ref a, ref b) => {
+                for h in &[a, b] {
+                    if let Node(_, ref y, _, _) = ***h { assert!(x <= y); }
+                }
+
+                let (ra, rb) = (a.check_invariants(), b.check_invariants());
+                assert!(ra >= rb);
+                assert_eq!(r, rb + 1);
+                r
+            }
+        }
+    }
+}
+
+// a tree of rank `r` has `r` children of ranks `r - 1` down to 0, none of them with a smaller root
+#[cfg(test)]
+fn check_binomial_tree<T: Ord>(t: &BinomialTree<T>) {
+    let BinomialTree(r, ref x, ref c) = *t;
+    assert_eq!(c.0.len(), r);
+
+    for (i, child) in c.0.iter().enumerate() {
+        let BinomialTree(rc, ref y, _) = **child;
+        assert_eq!(rc, r - 1 - i);
+        assert!(x <= y);
+        check_binomial_tree(child);
+    }
+}
+
+#[cfg(test)]
+impl<T: Ord> BinomialHeap<T> {
+    pub(crate) fn check_invariants(&self) {
+        for (i, t) in self.0.iter().enumerate() {
+            if i > 0 { assert!(rank(&self.0[i - 1]) < rank(t)); }
+            check_binomial_tree(t);
+        }
+    }
+}
+
+#[cfg(test)]
+fn check_skew_binomial_tree<T: Ord>(t: &SkewBinomialTree<T>) {
+    let SkewBinomialTree(r, ref x, ref xs, ref c) = *t;
+    assert!(xs.iter().count() <= r);
+    assert!(xs.iter().all(|y| x <= y));
+    assert_eq!(c.iter().count(), r);
+
+    for (i, child) in c.iter().enumerate() {
+        let SkewBinomialTree(rc, ref y, _, _) = **child;
+        assert_eq!(rc, r - 1 - i);
+        assert!(x <= y);
+        check_skew_binomial_tree(child);
+    }
+}
+
+#[cfg(test)]
+impl<T: Ord> SkewBinomialHeap<T> {
+    pub(crate) fn check_invariants(&self) {
+        let ranks: Vec<usize> = self.0.iter().map(|t| skew_rank(t)).collect();
+
+        for i in 1..ranks.len() {
+            if i == 1 { assert!(ranks[0] <= ranks[1]); } else { assert!(ranks[i - 1] < ranks[i]); }
+        }
+
+        for t in self.0.iter() {
+            check_skew_binomial_tree(t);
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T: Ord + Clone, H: Heap<T>> ExplicitMin<T, H> {
+    pub(crate) fn check_invariants(&self) {
+        assert!(self.min == self.heap.try_find_min().ok());
+    }
+}
+
+// no child is empty and none has a smaller root than its parent
+#[cfg(test)]
+impl<T: Ord> PairingHeap<T> {
+    pub(crate) fn check_invariants(&self) {
+        let mut stack = vec![self];
+
+        while let Some(h) = stack.pop() {
+            if let PairingHeap::Tree(ref x, ref hs) = *h {
+                for c in hs.iter() {
+                    match **c {
+                        PairingHeap::Tree(ref y, _) => assert!(x <= y),
+                        PairingHeap::Empty => panic!("empty child")
+                    }
+
+                    stack.push(c);
+                }
+            }
+        }
+    }
+}
+
+// the elements are in order, duplicates included
+#[cfg(test)]
+impl<T: Ord> SplayHeap<T> {
+    pub(crate) fn check_invariants(&self) {
+        let xs: Vec<&T> = self.0.iter().collect();
+        assert!(xs.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+// sorts pseudo-random sequences (with plenty of duplicates) through `H`, also merging heaps
+// built from both halves and checking `try_pop_min` against `find_min` and `delete_min`
+#[cfg(test)]
+fn check_heapsort<H: Heap<u32> + IntoIterator<Item=u32> + Clone>() {
+    use model::Rng;
+
+    let mut rng = Rng(3141592653);
+
+    for n in vec![0, 1, 2, 3, 10, 100, 1000] {
+        let xs: Vec<u32> = (0..n).map(|_| rng.next() % 500).collect();
+        let mut sorted = xs.clone();
+        sorted.sort();
+
+        let h = xs.iter().fold(H::empty(), |h, x| h.insert(*x));
+        match h.try_pop_min() {
+            Ok((x, rest)) => {
+                assert_eq!(x, h.find_min());
+                assert_eq!(rest.into_iter().collect::<Vec<u32>>(), h.delete_min().into_iter().collect::<Vec<u32>>());
+            },
+            Err(_) => assert!(h.is_empty())
+        }
+        assert_eq!(h.into_iter().collect::<Vec<u32>>(), sorted);
+
+        let (a, b) = xs.split_at(n / 2);
+        let ha = a.iter().fold(H::empty(), |h, x| h.insert(*x));
+        let hb = b.iter().fold(H::empty(), |h, x| h.insert(*x));
+        assert_eq!(ha.merge(&hb).into_iter().collect::<Vec<u32>>(), sorted);
     }
 }
 
@@ -248,8 +1178,32 @@ fn leftistheap() {
     assert!(h.is_empty());
     assert!(!h2.is_empty());
 
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+    assert_eq!(h2.try_find_min(), Ok(1));
+
     assert_eq!(h2.find_min(), 1);
     assert_eq!(h2.delete_min(), h.insert(10).insert(9).insert(8).insert(11).insert(4));
+
+    assert_eq!(h2.clone().into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    let mut h3: LeftistHeap<usize> = vec![10, 9, 8].into_iter().collect();
+    h3.extend(vec![11, 1, 4]);
+    assert_eq!(h3.into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+}
+
+#[test]
+fn long_leftistheap() {
+    let n = 1000000;
+
+    let mut h: LeftistHeap<usize> = Heap::empty();
+    for i in (0..n).rev() {
+        h = h.insert(i);
+    }
+
+    // every element ends up on the left spine
+    assert_eq!(h.find_min(), 0);
+    assert_eq!(h.clone().into_iter().count(), n);
 }
 
 #[test]
@@ -260,6 +1214,200 @@ fn binomialheap() {
     assert!(h.is_empty());
     assert!(!h2.is_empty());
 
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+    assert_eq!(h2.try_find_min(), Ok(1));
+
     assert_eq!(h2.find_min(), 1);
     assert_eq!(h2.delete_min(), h.insert(10).insert(9).insert(8).insert(11).insert(4));
+    assert_eq!(h2.try_pop_min(), Ok((1, h2.delete_min())));
+
+    assert_eq!(h2.clone().into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    let mut h3: BinomialHeap<usize> = vec![10, 9, 8].into_iter().collect();
+    h3.extend(vec![11, 1, 4]);
+    assert_eq!(h3.into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+}
+
+// merging two heaps whose first trees have the same rank links them and goes on with the rest
+// of both, without dropping the tree after either one
+#[test]
+fn binomialheap_merge() {
+    for n in 1..9 {
+        for m in 1..9 {
+            let h1: BinomialHeap<usize> = (0..n).collect();
+            let h2: BinomialHeap<usize> = (n..n + m).collect();
+            let h = h1.merge(&h2);
+            h.check_invariants();
+            assert_eq!(h.into_iter().collect::<Vec<usize>>(), (0..n + m).collect::<Vec<usize>>());
+        }
+    }
+}
+
+#[test]
+fn heap_layout() {
+    use tree_layout::{absolute, design, Layout};
+
+    fn xs<T: Clone>(t: &Layout<T>) -> Vec<(T, f64)> {
+        let mut v = vec![(t.label.clone(), t.x)];
+
+        for c in &t.children {
+            v.extend(xs(c));
+        }
+
+        v
+    }
+
+    // seven elements are trees of ranks 0, 1 and 2, whose children are in decreasing order of rank
+    let h: BinomialHeap<usize> = (0..7).collect();
+    let ts = h.trees();
+    assert_eq!(ts.iter().map(|t| xs(&design(*t).unwrap()).len()).collect::<Vec<usize>>(), vec![1, 2, 4]);
+    assert_eq!(xs(&absolute(&design(ts[2]).unwrap())), vec![(0, 0.0), (2, -0.5), (3, -0.5), (1, 0.5)]);
+
+    let h: SkewBinomialHeap<usize> = (0..7).collect();
+    let sizes: usize = h.trees().iter().map(|t| xs(&design(*t).unwrap()).len()).sum();
+    assert!(sizes <= 7);
+    assert_eq!(design(h.trees()[0]).unwrap().label, h.find_min());
+
+    let h: SplayHeap<usize> = (0..7).collect();
+    let l = design(h.tree()).unwrap();
+    assert_eq!(xs(&l).len(), 7);
+    assert_eq!(design(h.delete_min().tree()).map(|l| xs(&l).len()), Some(6));
+}
+
+#[test]
+fn skewbinomialheap() {
+    let h: SkewBinomialHeap<usize> = Heap::empty();
+    let h2: SkewBinomialHeap<usize> = h.insert(10).insert(9).insert(8).insert(11).insert(1).insert(4);
+
+    assert!(h.is_empty());
+    assert!(!h2.is_empty());
+
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+    assert_eq!(h2.try_find_min(), Ok(1));
+
+    assert_eq!(h2.find_min(), 1);
+    assert_eq!(h2.delete_min().into_iter().collect::<Vec<usize>>(), vec![4, 8, 9, 10, 11]);
+    assert_eq!(h2.clone().into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    check_heapsort::<SkewBinomialHeap<u32>>();
+}
+
+#[test]
+fn explicitmin() {
+    let h: ExplicitMin<usize, BinomialHeap<usize>> = Heap::empty();
+    let h2 = h.insert(10).insert(9).insert(8).insert(11).insert(1).insert(4);
+
+    assert!(h.is_empty());
+    assert!(!h2.is_empty());
+
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+
+    assert_eq!(h2.find_min(), 1);
+    assert_eq!(h2.delete_min().find_min(), 4);
+    assert_eq!(h2.merge(&h.insert(0)).find_min(), 0);
+
+    let l: LeftistHeap<usize> = vec![3, 1, 2].into_iter().collect();
+    assert_eq!(ExplicitMin::new(l).find_min(), 1);
+
+    check_heapsort::<ExplicitMin<u32, LeftistHeap<u32>>>();
+    check_heapsort::<ExplicitMin<u32, SkewBinomialHeap<u32>>>();
+}
+
+#[test]
+fn pairingheap() {
+    let h: PairingHeap<usize> = Heap::empty();
+    let h2: PairingHeap<usize> = h.insert(10).insert(9).insert(8).insert(11).insert(1).insert(4);
+
+    assert!(h.is_empty());
+    assert!(!h2.is_empty());
+
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+    assert_eq!(h2.try_find_min(), Ok(1));
+
+    assert_eq!(h2.find_min(), 1);
+    assert_eq!(h2.delete_min().find_min(), 4);
+    assert_eq!(h2.delete_min().into_iter().collect::<Vec<usize>>(), vec![4, 8, 9, 10, 11]);
+
+    assert_eq!(h2.clone().into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    let mut h3: PairingHeap<usize> = vec![10, 9, 8].into_iter().collect();
+    h3.extend(vec![11, 1, 4]);
+    assert_eq!(h3.into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    check_heapsort::<PairingHeap<u32>>();
+}
+
+#[test]
+fn long_pairingheap() {
+    let n = 1000000;
+
+    // decreasing insertions nest the heaps, increasing ones give the root a child per element
+    let mut h: PairingHeap<usize> = Heap::empty();
+    let mut h2: PairingHeap<usize> = Heap::empty();
+    for i in 0..n {
+        h = h.insert(n - i);
+        h2 = h2.insert(i);
+    }
+
+    assert_eq!(h.find_min(), 1);
+    assert_eq!(h2.delete_min().find_min(), 1);
+}
+
+#[test]
+fn splayheap() {
+    let h: SplayHeap<usize> = Heap::empty();
+    let h2: SplayHeap<usize> = h.insert(10).insert(9).insert(8).insert(11).insert(1).insert(4);
+
+    assert!(h.is_empty());
+    assert!(!h2.is_empty());
+
+    assert_eq!(h.try_find_min(), Err(EmptyError));
+    assert_eq!(h.try_delete_min(), Err(EmptyError));
+    assert_eq!(h2.try_find_min(), Ok(1));
+
+    assert_eq!(h2.find_min(), 1);
+    assert_eq!(h2.delete_min().find_min(), 4);
+    assert_eq!(h2.delete_min().into_iter().collect::<Vec<usize>>(), vec![4, 8, 9, 10, 11]);
+
+    assert_eq!(h2.clone().into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    let mut h3: SplayHeap<usize> = vec![10, 9, 8].into_iter().collect();
+    h3.extend(vec![11, 1, 4]);
+    assert_eq!(h3.into_iter().collect::<Vec<usize>>(), vec![1, 4, 8, 9, 10, 11]);
+
+    check_heapsort::<SplayHeap<u32>>();
+}
+
+#[test]
+fn long_splayheap() {
+    let n = 1000000;
+
+    // every element ends up on the left spine
+    let mut h: SplayHeap<usize> = Heap::empty();
+    for i in 0..n {
+        h = h.insert(i);
+    }
+
+    assert_eq!(h.find_min(), 0);
+    assert_eq!(h.delete_min().delete_min().find_min(), 2);
+}
+
+#[test]
+fn long_splayheap_new_minimum() {
+    let n = 1000000;
+
+    // a new minimum is partitioned down the whole left spine, and merging walks all of it
+    let h = (1..n).fold(SplayHeap::empty(), |h: SplayHeap<usize>, i| h.insert(i));
+    let h2 = h.insert(0);
+    assert_eq!(h2.find_min(), 0);
+    assert_eq!(h2.delete_min().find_min(), 1);
+
+    let single: SplayHeap<usize> = SplayHeap::empty().insert(n);
+    assert_eq!(h.merge(&single).find_min(), 1);
+    assert_eq!(single.merge(&h).find_min(), 1);
+    assert_eq!(single.merge(&h2).delete_min().find_min(), 1);
 }
@@ -1,6 +1,22 @@
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+pub mod dot;
+pub mod error;
 pub mod heap;
 pub mod map;
+#[cfg(test)]
+mod model;
+pub mod ptr;
+pub mod queue;
+pub mod random_access_list;
+pub mod red_black_tree;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod set;
+pub mod snapshot;
 pub mod stack;
 pub mod tree;
 pub mod tree_layout;
@@ -1,9 +1,9 @@
 extern crate okasaki;
 
-use std::rc::Rc;
-
 use okasaki::heap::*;
 use okasaki::map::*;
+use okasaki::ptr::Ptr;
+use okasaki::red_black_tree::RedBlackTree;
 use okasaki::set::*;
 use okasaki::stack::*;
 use okasaki::stack::List::*;
@@ -25,7 +25,7 @@ fn list() {
                 let l: List<List<usize>> = Stack::empty();
                 l.cons(Nil)
             }
-            Cons(h, ref t) => Cons(Cons(h, t.clone()), Rc::new(suffixes((**t).clone())))
+            Cons(h, ref t) => Cons(Cons(h, t.clone()), Ptr::new(suffixes((**t).clone())))
         }
     }
 
@@ -43,6 +43,15 @@ fn tree() {
     println!("{}", t2);
 }
 
+fn red_black_tree() {
+    let t: RedBlackTree<usize> = Set::empty();
+    let t2 = t.insert(1).insert(2).insert(3).insert(4)
+        .insert(5).insert(6).insert(7);
+
+    println!("{:?}", t2);
+    println!("{}", t2);
+}
+
 fn map() {
     let m: Tree<(String, usize)> = Map::empty();
     let m2 = m.bind("hello".to_string(), 0)
@@ -94,6 +103,7 @@ fn trie() {
 fn main() {
     list();
     tree();
+    red_black_tree();
     map();
     heap();
     trie();
@@ -1,12 +1,66 @@
-use std::rc::Rc;
+use std::ops;
 
-use tree::Tree;
+use ptr::Ptr;
+use tree::{rebuild, Tree};
 use tree::Tree::{Node, Tip};
 
 pub trait Map<K, V> {
     fn empty() -> Self;
+
+    // binds the key to the value, replacing the value of an existing binding
     fn bind(&self, K, V) -> Self;
-    fn lookup(&self, K) -> V;
+    fn get(&self, K) -> Option<V>;
+
+    // returns a version without a binding for the given key, or the original map if there was none
+    fn unbind(&self, K) -> Self;
+
+    fn lookup(&self, k: K) -> V {
+        match self.get(k) {
+            Some(v) => v,
+            None => panic!("element does not exist")
+        }
+    }
+
+    fn lookup_default(&self, k: K, default: V) -> V {
+        self.get(k).unwrap_or(default)
+    }
+
+    // binds the key to `f(old, v)` if it is already bound to `old`, and to `v` otherwise
+    fn insert_with<F>(&self, k: K, v: V, f: F) -> Self
+        where F: FnOnce(V, V) -> V, K: Clone, Self: Sized {
+        match self.get(k.clone()) {
+            Some(old) => self.bind(k, f(old, v)),
+            None => self.bind(k, v)
+        }
+    }
+
+    // applies `f` to the value bound to the key, or returns the original map if there is none
+    fn adjust<F>(&self, k: K, f: F) -> Self
+        where F: FnOnce(V) -> V, K: Clone, Self: Clone {
+        match self.get(k.clone()) {
+            Some(v) => self.bind(k, f(v)),
+            None => self.clone()
+        }
+    }
+
+    // `f` gets the value bound to the key, if any, and returns the new one, or `None` to unbind it
+    fn alter<F>(&self, k: K, f: F) -> Self
+        where F: FnOnce(Option<V>) -> Option<V>, K: Clone, Self: Sized {
+        match f(self.get(k.clone())) {
+            Some(v) => self.bind(k, v),
+            None => self.unbind(k)
+        }
+    }
+}
+
+// the ordered queries of `set::OrderedSet`, looking bindings up by key; they are named after
+// bindings so that a `Tree<(K, V)>` can be used through both traits at once
+pub trait OrderedMap<K, V>: Map<K, V> {
+    fn min_binding(&self) -> Option<(K, V)>;
+    fn max_binding(&self) -> Option<(K, V)>;
+    fn floor_binding(&self, &K) -> Option<(K, V)>;
+    fn ceiling_binding(&self, &K) -> Option<(K, V)>;
+    fn range_bindings<'a>(&'a self, ops::Range<K>) -> Box<dyn Iterator<Item=(&'a K, &'a V)> + 'a> where K: 'a, V: 'a;
 }
 
 impl<K: Ord + Clone, V: Clone> Map<K, V> for Tree<(K, V)> {
@@ -14,29 +68,109 @@ impl<K: Ord + Clone, V: Clone> Map<K, V> for Tree<(K, V)> {
         Tip
     }
 
+    // unlike `Set::insert`, which keeps the original tree, binding an existing key copies the
+    // search path to replace its value
     fn bind(&self, k: K, v: V) -> Self {
-        match *self {
-            Tip =>
-                Node(Rc::new(Tip), (k, v), Rc::new(Tip)),
-            Node(ref l, (ref k1, ref v1), ref r) if k < *k1 =>
-                Node(Rc::new(l.bind(k, v)), (k1.clone(), v1.clone()), r.clone()),
-            Node(ref l, (ref k1, ref v1), ref r) if k > *k1 =>
-                Node(l.clone(), (k1.clone(), v1.clone()), Rc::new(r.bind(k, v))),
-            _ =>
-                self.clone()
+        let mut path = vec![];
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return rebuild(path, Node(Ptr::new(Tip), (k, v), Ptr::new(Tip))),
+                Node(ref l, (ref k1, _), _) if k < *k1 => { path.push((t, true)); t = l; },
+                Node(_, (ref k1, _), ref r) if k > *k1 => { path.push((t, false)); t = r; },
+                Node(ref l, _, ref r) => return rebuild(path, Node(l.clone(), (k, v), r.clone()))
+            }
+        }
+    }
+
+    fn get(&self, x: K) -> Option<V> {
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return None,
+                Node(ref l, (ref k, _), _) if x < *k => t = l,
+                Node(_, (ref k, _), ref r) if x > *k => t = r,
+                Node(_, (_, ref v), _) => return Some(v.clone())
+            }
         }
     }
 
-    fn lookup(&self, x: K) -> V {
-        match *self {
-            Tip => panic!("element does not exist"),
-            Node(ref l, (ref k, _), _) if x < *k => l.lookup(x),
-            Node(_, (ref k, _), ref r) if x > *k => r.lookup(x),
-            Node(_, (_, ref v), _) => v.clone()
+    fn unbind(&self, x: K) -> Tree<(K, V)> {
+        let mut path = vec![];
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return self.clone(),
+                Node(ref l, (ref k, _), _) if x < *k => { path.push((t, true)); t = l; },
+                Node(_, (ref k, _), ref r) if x > *k => { path.push((t, false)); t = r; },
+                Node(ref l, _, ref r) => return rebuild(path, Tree::glue(l, r))
+            }
         }
     }
 }
 
+fn key<K, V>(e: &(K, V)) -> &K {
+    &e.0
+}
+
+impl<K: Ord + Clone, V: Clone> OrderedMap<K, V> for Tree<(K, V)> {
+    fn min_binding(&self) -> Option<(K, V)> {
+        self.min_elem().cloned()
+    }
+
+    fn max_binding(&self) -> Option<(K, V)> {
+        self.max_elem().cloned()
+    }
+
+    fn floor_binding(&self, k: &K) -> Option<(K, V)> {
+        self.floor_by(k, key).cloned()
+    }
+
+    fn ceiling_binding(&self, k: &K) -> Option<(K, V)> {
+        self.ceiling_by(k, key).cloned()
+    }
+
+    fn range_bindings<'a>(&'a self, r: ops::Range<K>) -> Box<dyn Iterator<Item=(&'a K, &'a V)> + 'a> where K: 'a, V: 'a {
+        Box::new(self.range_by(r.start, r.end, key).map(|e| (&e.0, &e.1)))
+    }
+}
+
+// exercises the default methods, which every implementor shares
+#[cfg(test)]
+pub(crate) fn check_entry_api<M: Map<String, usize> + Clone>() {
+    let m: M = Map::empty();
+    let m2 = m.bind("foo".to_string(), 1).bind("bar".to_string(), 2);
+
+    assert_eq!(m2.bind("foo".to_string(), 3).lookup("foo".to_string()), 3);
+
+    assert_eq!(m2.lookup_default("foo".to_string(), 0), 1);
+    assert_eq!(m2.lookup_default("baz".to_string(), 0), 0);
+
+    let m3 = m2.insert_with("foo".to_string(), 10, |old, v| old * 100 + v)
+        .insert_with("baz".to_string(), 10, |old, v| old * 100 + v);
+    assert_eq!(m3.lookup("foo".to_string()), 110);
+    assert_eq!(m3.lookup("baz".to_string()), 10);
+
+    let m4 = m2.adjust("bar".to_string(), |v| v + 1).adjust("baz".to_string(), |v| v + 1);
+    assert_eq!(m4.lookup("bar".to_string()), 3);
+    assert_eq!(m4.get("baz".to_string()), None);
+
+    let m5 = m2.alter("foo".to_string(), |v| v.map(|v| v * 2))
+        .alter("bar".to_string(), |_| None)
+        .alter("baz".to_string(), |v| Some(v.unwrap_or(7)));
+    assert_eq!(m5.lookup("foo".to_string()), 2);
+    assert_eq!(m5.get("bar".to_string()), None);
+    assert_eq!(m5.lookup("baz".to_string()), 7);
+
+    // the original stays untouched
+    assert_eq!(m2.lookup("foo".to_string()), 1);
+    assert_eq!(m2.lookup("bar".to_string()), 2);
+    assert_eq!(m2.get("baz".to_string()), None);
+}
+
 #[test]
 fn treemap() {
     let m: Tree<(&str, usize)> = Map::empty();
@@ -49,4 +183,58 @@ fn treemap() {
     assert_eq!(m2.lookup("world"), 1);
     assert_eq!(m2.lookup("foo"), 2);
     assert_eq!(m2.lookup("bar"), 3);
+
+    assert_eq!(m2.get("foo"), Some(2));
+    assert_eq!(m2.get("baz"), None);
+    assert_eq!(m.get("foo"), None);
+
+    let m3 = m2.unbind("hello").unbind("bar");
+
+    assert_eq!(m3.get("hello"), None);
+    assert_eq!(m3.get("bar"), None);
+    assert_eq!(m3.lookup("world"), 1);
+    assert_eq!(m3.lookup("foo"), 2);
+    assert_eq!(m2.lookup("hello"), 0);
+    assert_eq!(m2.unbind("baz"), m2);
+    assert_eq!(m2.bind("foo", 4).lookup("foo"), 4);
+    assert_eq!(m2.bind("foo", 4).iter().count(), 4);
+
+    assert_eq!(m2.iter().map(|e| e.0).collect::<Vec<&str>>(), vec!["bar", "foo", "hello", "world"]);
+
+    check_entry_api::<Tree<(String, usize)>>();
+
+    // collecting binds the pairs in order, so the last value of "foo" replaces the first one
+    let m4: Tree<(&str, usize)> = vec![("foo", 1), ("bar", 2), ("foo", 3), ("bar", 2)].into_iter().collect();
+    assert_eq!(m4.iter().cloned().collect::<Vec<(&str, usize)>>(), vec![("bar", 2), ("foo", 3)]);
+    assert_eq!(m4.unbind("foo").get("foo"), None);
+}
+
+#[test]
+fn treemap_order() {
+    use set::OrderedSet;
+
+    let m: Tree<(&str, usize)> = Map::empty();
+    let m2 = m.bind("hello", 0)
+        .bind("world", 1)
+        .bind("foo", 2)
+        .bind("bar", 3);
+
+    assert_eq!(m2.min_binding(), Some(("bar", 3)));
+    assert_eq!(m2.max_binding(), Some(("world", 1)));
+    assert_eq!(m.min_binding(), None);
+    assert_eq!(m2.min(), Some(("bar", 3)));
+    assert_eq!(m2.floor(&("foo", 100)), Some(("foo", 2)));
+
+    assert_eq!(m2.floor_binding(&"goodbye"), Some(("foo", 2)));
+    assert_eq!(m2.floor_binding(&"hello"), Some(("hello", 0)));
+    assert_eq!(m2.floor_binding(&"a"), None);
+    assert_eq!(m2.ceiling_binding(&"goodbye"), Some(("hello", 0)));
+    assert_eq!(m2.ceiling_binding(&"zebra"), None);
+
+    assert_eq!(m2.range_bindings("c".."i").collect::<Vec<(&&str, &usize)>>(), vec![(&"foo", &2), (&"hello", &0)]);
+    assert_eq!(m2.range_bindings("bar".."bar").count(), 0);
+
+    let m3 = Tree::from_sorted_iter(m2.iter().cloned());
+    assert_eq!(m3.iter().collect::<Vec<&(&str, usize)>>(), m2.iter().collect::<Vec<&(&str, usize)>>());
+    assert_eq!(m3.lookup("foo"), 2);
 }
@@ -0,0 +1,345 @@
+// Model-based tests: every implementor of `Stack`, `Set`, `Map` and `Heap` runs a pseudo-random
+// sequence of operations next to a collection from the standard library, and the two have to agree
+// after every step. Each operation is applied to one of a pool of earlier versions, so that old
+// versions keep being used after new ones have been derived from them, and the structural
+// invariants of every implementor are checked on the way.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+
+use heap::{BinomialHeap, ExplicitMin, Heap, LeftistHeap, PairingHeap, SkewBinomialHeap, SplayHeap};
+use map::Map;
+use random_access_list::{RandomAccess, RandomAccessList};
+use red_black_tree::{self, RedBlackTree};
+use set::Set;
+use stack::{List, Stack};
+use tree::Tree;
+use trie::{ByteTrie, PatriciaTrie};
+
+const STEPS: usize = 3000;
+const VERSIONS: usize = 16;
+
+// appending and merging two versions doubles their size, so they are skipped above this one
+const MAX_SIZE: usize = 400;
+
+// xorshift, so that the tests are deterministic without pulling in a rng
+pub(crate) struct Rng(pub(crate) u32);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        self.next() as usize % n
+    }
+}
+
+// keeps a new version, forgetting a random one once the pool is full
+fn keep<S, M>(rng: &mut Rng, versions: &mut Vec<(S, M)>, v: (S, M)) {
+    if versions.len() == VERSIONS {
+        let i = rng.below(VERSIONS);
+        versions.swap_remove(i);
+    }
+
+    versions.push(v);
+}
+
+fn stack_contents<S: Stack<u32> + Clone>(s: &S) -> Vec<u32> {
+    let mut xs = vec![];
+    let mut s = s.clone();
+
+    while let Ok(x) = s.try_head() {
+        xs.push(x);
+        s = s.tail();
+    }
+
+    xs
+}
+
+// the model is a vector whose first element is the head
+fn check_stack<S: Stack<u32> + Clone>(check: fn(&S, &[u32])) {
+    let mut rng = Rng(88172645);
+    let mut versions: Vec<(S, Vec<u32>)> = vec![(S::empty(), vec![])];
+
+    for _ in 0..STEPS {
+        let (s, mut m) = versions[rng.below(versions.len())].clone();
+
+        let s = match rng.below(6) {
+            0 | 1 => {
+                let x = rng.next();
+                m.insert(0, x);
+                s.cons(x)
+            },
+            2 => match s.try_tail() {
+                Ok(t) => {
+                    m.remove(0);
+                    t
+                },
+                Err(_) => {
+                    assert!(m.is_empty());
+                    s
+                }
+            },
+            3 => {
+                let (ref o, ref mo) = versions[rng.below(versions.len())];
+
+                if m.len() + mo.len() <= MAX_SIZE {
+                    m.extend(mo.iter().cloned());
+                    s.append(o)
+                } else {
+                    s
+                }
+            },
+            4 if !m.is_empty() => {
+                let (i, x) = (rng.below(m.len()), rng.next());
+                m[i] = x;
+                s.update(i, x)
+            },
+            _ => s
+        };
+
+        assert_eq!(s.is_empty(), m.is_empty());
+        assert_eq!(s.try_head().ok(), m.first().cloned());
+        assert_eq!(stack_contents(&s), m);
+        check(&s, &m);
+
+        keep(&mut rng, &mut versions, (s, m));
+    }
+
+    for &(ref s, ref m) in &versions {
+        assert_eq!(&stack_contents(s), m);
+    }
+}
+
+// the elements are drawn from a small range so that they are often already members
+const ELEMENTS: u32 = 64;
+
+fn check_set<S: Set<u32> + Clone>(check: fn(&S, &BTreeSet<u32>)) {
+    let mut rng = Rng(521288629);
+    let mut versions: Vec<(S, BTreeSet<u32>)> = vec![(S::empty(), BTreeSet::new())];
+
+    for _ in 0..STEPS {
+        let (s, mut m) = versions[rng.below(versions.len())].clone();
+        let (ref o, ref mo) = versions[rng.below(versions.len())].clone();
+        let x = rng.next() % ELEMENTS;
+
+        assert_eq!(s.is_subset(o), m.is_subset(mo));
+
+        let (s, m) = match rng.below(8) {
+            0 | 1 | 2 => {
+                m.insert(x);
+                (s.insert(x), m)
+            },
+            3 | 4 => {
+                m.remove(&x);
+                (s.delete(x), m)
+            },
+            5 => (s.union(o), m.union(mo).cloned().collect()),
+            6 => (s.intersection(o), m.intersection(mo).cloned().collect()),
+            _ => (s.difference(o), m.difference(mo).cloned().collect())
+        };
+
+        for x in 0..ELEMENTS {
+            assert_eq!(s.member(x), m.contains(&x));
+        }
+
+        check(&s, &m);
+
+        keep(&mut rng, &mut versions, (s, m));
+    }
+
+    for &(ref s, ref m) in &versions {
+        check(s, m);
+    }
+}
+
+// every string of up to three chars over an alphabet mixing one, two and three byte chars, so
+// that keys often share prefixes and the tries split and merge nodes inside multi-byte chars
+fn map_keys() -> Vec<String> {
+    let alphabet = ['a', 'b', '\u{e9}', '\u{65e5}'];
+    let mut keys = vec![String::new()];
+    let mut last = vec![String::new()];
+
+    for _ in 0..3 {
+        last = last.iter().flat_map(|k| alphabet.iter().map(move |c| format!("{}{}", k, c))).collect();
+        keys.extend(last.iter().cloned());
+    }
+
+    keys
+}
+
+fn check_map<M: Map<String, u32> + Clone>(check: fn(&M, &BTreeMap<String, u32>)) {
+    let keys = map_keys();
+    let mut rng = Rng(2463534242);
+    let mut versions: Vec<(M, BTreeMap<String, u32>)> = vec![(M::empty(), BTreeMap::new())];
+
+    for _ in 0..STEPS {
+        let (s, mut m) = versions[rng.below(versions.len())].clone();
+        let k = keys[rng.below(keys.len())].clone();
+        let v = rng.next() % 1000;
+
+        let s = match rng.below(7) {
+            0 | 1 | 2 => {
+                m.insert(k.clone(), v);
+                s.bind(k, v)
+            },
+            3 => {
+                m.remove(&k);
+                s.unbind(k)
+            },
+            4 => {
+                *m.entry(k.clone()).or_insert(0) += v;
+                s.insert_with(k, v, |old, v| old + v)
+            },
+            5 => {
+                if let Some(old) = m.get_mut(&k) { *old *= 3; }
+                s.adjust(k, |old| old * 3)
+            },
+            _ => {
+                if m.remove(&k).is_none() { m.insert(k.clone(), v); }
+                s.alter(k, |old| if old.is_some() { None } else { Some(v) })
+            }
+        };
+
+        for k in &keys {
+            assert_eq!(s.get(k.clone()), m.get(k).cloned());
+        }
+
+        check(&s, &m);
+
+        keep(&mut rng, &mut versions, (s, m));
+    }
+
+    for &(ref s, ref m) in &versions {
+        check(s, m);
+    }
+}
+
+fn drain<H: Heap<u32> + Clone>(h: &H) -> Vec<u32> {
+    let mut xs = vec![];
+    let mut h = h.clone();
+
+    while let Ok(x) = h.try_find_min() {
+        xs.push(x);
+        h = h.delete_min();
+    }
+
+    xs
+}
+
+fn check_heap<H: Heap<u32> + Clone>(check: fn(&H)) {
+    let mut rng = Rng(3141592653);
+    let mut versions: Vec<(H, BinaryHeap<Reverse<u32>>)> = vec![(H::empty(), BinaryHeap::new())];
+
+    for _ in 0..STEPS {
+        let (h, mut m) = versions[rng.below(versions.len())].clone();
+
+        let h = match rng.below(6) {
+            0 | 1 | 2 => {
+                let x = rng.next() % 100;
+                m.push(Reverse(x));
+                h.insert(x)
+            },
+            3 | 4 => match h.try_delete_min() {
+                Ok(h) => {
+                    m.pop();
+                    h
+                },
+                Err(_) => {
+                    assert!(m.is_empty());
+                    h
+                }
+            },
+            _ => {
+                let (ref o, ref mo) = versions[rng.below(versions.len())];
+
+                if m.len() + mo.len() <= MAX_SIZE {
+                    m.extend(mo.iter().cloned());
+                    h.merge(o)
+                } else {
+                    h
+                }
+            }
+        };
+
+        assert_eq!(h.is_empty(), m.is_empty());
+        assert_eq!(h.try_find_min().ok(), m.peek().map(|x| x.0));
+        check(&h);
+
+        keep(&mut rng, &mut versions, (h, m));
+    }
+
+    for &(ref h, ref m) in &versions {
+        let mut sorted: Vec<u32> = m.iter().map(|x| x.0).collect();
+        sorted.sort();
+        assert_eq!(drain(h), sorted);
+    }
+}
+
+#[test]
+fn model_stacks() {
+    check_stack::<List<u32>>(|l, m| {
+        assert_eq!(l.iter().cloned().collect::<Vec<u32>>(), m);
+    });
+
+    check_stack::<RandomAccessList<u32>>(|l, m| {
+        assert_eq!(l.iter().cloned().collect::<Vec<u32>>(), m);
+
+        for i in 0..m.len() + 1 {
+            assert_eq!(l.get(i), m.get(i).cloned());
+        }
+    });
+}
+
+#[test]
+fn model_sets() {
+    check_set::<Tree<u32>>(|t, m| {
+        assert_eq!(t.iter().cloned().collect::<Vec<u32>>(), m.iter().cloned().collect::<Vec<u32>>());
+    });
+
+    check_set::<RedBlackTree<u32>>(|t, m| {
+        red_black_tree::check_invariants(t, None, None);
+        assert_eq!(t.iter().cloned().collect::<Vec<u32>>(), m.iter().cloned().collect::<Vec<u32>>());
+    });
+}
+
+#[test]
+fn model_maps() {
+    fn pairs(m: &BTreeMap<String, u32>) -> Vec<(String, u32)> {
+        m.iter().map(|(k, v)| (k.clone(), *v)).collect()
+    }
+
+    check_map::<Tree<(String, u32)>>(|t, m| {
+        assert_eq!(t.iter().cloned().collect::<Vec<(String, u32)>>(), pairs(m));
+    });
+
+    check_map::<RedBlackTree<(String, u32)>>(|t, m| {
+        red_black_tree::check_invariants(t, None, None);
+        assert_eq!(t.iter().cloned().collect::<Vec<(String, u32)>>(), pairs(m));
+    });
+
+    check_map::<PatriciaTrie<u32>>(|t, m| {
+        t.check_invariants();
+        assert_eq!(t.iter().map(|(k, v)| (k, *v)).collect::<Vec<(String, u32)>>(), pairs(m));
+    });
+
+    check_map::<ByteTrie<u32>>(|t, m| {
+        t.check_invariants();
+        let expected: Vec<(Vec<u8>, u32)> = m.iter().map(|(k, v)| (k.clone().into_bytes(), *v)).collect();
+        assert_eq!(t.iter().map(|(k, v)| (k, *v)).collect::<Vec<(Vec<u8>, u32)>>(), expected);
+    });
+}
+
+#[test]
+fn model_heaps() {
+    check_heap::<LeftistHeap<u32>>(|h| { h.check_invariants(); });
+    check_heap::<BinomialHeap<u32>>(|h| h.check_invariants());
+    check_heap::<SkewBinomialHeap<u32>>(|h| h.check_invariants());
+    check_heap::<PairingHeap<u32>>(|h| h.check_invariants());
+    check_heap::<SplayHeap<u32>>(|h| h.check_invariants());
+    check_heap::<ExplicitMin<u32, LeftistHeap<u32>>>(|h| h.check_invariants());
+}
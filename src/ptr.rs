@@ -0,0 +1,54 @@
+// Every structure in the crate shares its nodes through `Ptr`, which is `Rc` by default. Building
+// with the `sync` feature switches it to `Arc`, making all structures `Send` and `Sync` so that
+// snapshots can be shared between threads, at the cost of atomic reference counting.
+
+#[cfg(not(feature = "sync"))]
+pub use std::rc::Rc as Ptr;
+
+#[cfg(feature = "sync")]
+pub use std::sync::Arc as Ptr;
+
+#[cfg(feature = "sync")]
+#[test]
+fn shared_across_threads() {
+    use std::thread;
+
+    use heap::{BinomialHeap, Heap, LeftistHeap};
+    use map::Map;
+    use queue::{Queue, RealTimeQueue};
+    use set::Set;
+    use stack::{List, Stack};
+    use tree::Tree;
+    use trie::PatriciaTrie;
+
+    let l: List<usize> = Stack::empty();
+    let t: Tree<usize> = Set::empty();
+    let h: LeftistHeap<usize> = Heap::empty();
+    let b: BinomialHeap<usize> = Heap::empty();
+    let p: PatriciaTrie<usize> = Map::empty();
+    let q: RealTimeQueue<usize> = Queue::empty();
+
+    let l = Ptr::new(l.cons(3).cons(2).cons(1));
+    let t = Ptr::new(t.insert(2).insert(1).insert(3));
+    let h = Ptr::new(h.insert(2).insert(1).insert(3));
+    let b = Ptr::new(b.insert(2).insert(1).insert(3));
+    let p = Ptr::new(p.bind("one".to_string(), 1).bind("two".to_string(), 2));
+    let q = Ptr::new(q.snoc(1).snoc(2).snoc(3));
+
+    let handles: Vec<thread::JoinHandle<()>> = (0..4).map(|_| {
+        let (l, t, h, b, p, q) = (l.clone(), t.clone(), h.clone(), b.clone(), p.clone(), q.clone());
+
+        thread::spawn(move || {
+            assert_eq!(l.head(), 1);
+            assert!(t.member(3));
+            assert_eq!(h.find_min(), 1);
+            assert_eq!(b.find_min(), 1);
+            assert_eq!(p.lookup("two".to_string()), 2);
+            assert_eq!(q.tail().head(), 2);
+        })
+    }).collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
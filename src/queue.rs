@@ -0,0 +1,404 @@
+#[cfg(not(feature = "sync"))]
+use std::cell::{RefCell, RefMut};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem;
+#[cfg(feature = "sync")]
+use std::sync::{Mutex, MutexGuard};
+
+use error::EmptyError;
+use ptr::Ptr;
+use stack::List;
+use stack::List::{Cons, Nil};
+use stack::Stack;
+
+pub trait Queue<T> {
+    fn empty() -> Self;
+    fn is_empty(&self) -> bool;
+
+    fn snoc(&self, T) -> Self;
+    fn try_head(&self) -> Result<T, EmptyError>;
+    fn try_tail(&self) -> Result<Self, EmptyError> where Self: Sized;
+
+    fn head(&self) -> T {
+        match self.try_head() {
+            Ok(x) => x,
+            Err(_) => panic!("head of empty queue")
+        }
+    }
+
+    fn tail(&self) -> Self where Self: Sized {
+        match self.try_tail() {
+            Ok(q) => q,
+            Err(_) => panic!("tail of empty queue")
+        }
+    }
+}
+
+// drains a queue from front to back
+pub struct IntoIter<T, Q> {
+    queue: Q,
+    marker: PhantomData<T>,
+}
+
+impl<T, Q: Queue<T>> Iterator for IntoIter<T, Q> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match (self.queue.try_head(), self.queue.try_tail()) {
+            (Ok(x), Ok(q)) => {
+                self.queue = q;
+                Some(x)
+            },
+            _ => None
+        }
+    }
+}
+
+// Section 5.2: the front list holds the first elements in order and the rear list holds the
+// remaining ones reversed, the front is only empty if the whole queue is
+#[derive(Clone, Debug)]
+pub struct BatchedQueue<T> {
+    front: List<T>,
+    rear: List<T>,
+}
+
+impl<T: Clone> BatchedQueue<T> {
+    fn check(front: List<T>, rear: List<T>) -> BatchedQueue<T> {
+        if front.is_empty() {
+            BatchedQueue { front: rear.reverse(), rear: Nil }
+        } else {
+            BatchedQueue { front: front, rear: rear }
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for BatchedQueue<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> BatchedQueue<T> {
+        let mut q = BatchedQueue::empty();
+        q.extend(iter);
+        q
+    }
+}
+
+impl<T: Clone> Extend<T> for BatchedQueue<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.snoc(x);
+        }
+    }
+}
+
+impl<T: Clone> IntoIterator for BatchedQueue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, BatchedQueue<T>>;
+
+    fn into_iter(self) -> IntoIter<T, BatchedQueue<T>> {
+        IntoIter { queue: self, marker: PhantomData }
+    }
+}
+
+impl<T: Clone> Queue<T> for BatchedQueue<T> {
+    fn empty() -> BatchedQueue<T> {
+        BatchedQueue { front: Nil, rear: Nil }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.front.is_empty()
+    }
+
+    fn snoc(&self, x: T) -> BatchedQueue<T> {
+        BatchedQueue::check(self.front.clone(), self.rear.cons(x))
+    }
+
+    fn try_head(&self) -> Result<T, EmptyError> {
+        self.front.try_head()
+    }
+
+    fn try_tail(&self) -> Result<BatchedQueue<T>, EmptyError> {
+        let front = try!(self.front.try_tail());
+        Ok(BatchedQueue::check(front, self.rear.clone()))
+    }
+}
+
+// memoized stream cells are only shared between threads with the `sync` feature
+#[cfg(not(feature = "sync"))]
+type Lock<T> = RefCell<T>;
+
+#[cfg(not(feature = "sync"))]
+fn lock<T>(c: &RefCell<T>) -> RefMut<T> {
+    c.borrow_mut()
+}
+
+#[cfg(feature = "sync")]
+type Lock<T> = Mutex<T>;
+
+#[cfg(feature = "sync")]
+fn lock<T>(c: &Mutex<T>) -> MutexGuard<T> {
+    c.lock().unwrap()
+}
+
+// A memoized lazy list. The only suspension the real-time queue ever builds is a rotation, so
+// instead of boxing arbitrary closures a stream cell is either an already evaluated node or the
+// pending `rotate(f, r, a)` call.
+#[derive(Clone, Debug)]
+struct Stream<T>(Ptr<Lock<StreamCell<T>>>);
+
+#[derive(Clone, Debug)]
+enum StreamNode<T> {
+    Nil,
+    Cons(T, Stream<T>),
+}
+
+#[derive(Debug)]
+enum StreamCell<T> {
+    Forced(StreamNode<T>),
+    Rotate(Stream<T>, List<T>, Stream<T>),
+}
+
+impl<T: Clone> Stream<T> {
+    fn nil() -> Stream<T> {
+        Stream(Ptr::new(Lock::new(StreamCell::Forced(StreamNode::Nil))))
+    }
+
+    fn cons(x: T, s: Stream<T>) -> Stream<T> {
+        Stream(Ptr::new(Lock::new(StreamCell::Forced(StreamNode::Cons(x, s)))))
+    }
+
+    // rotate(f, r, a) = f ++ reverse(r) ++ a, where |r| = |f| + 1, one step at a time
+    fn rotate(f: Stream<T>, r: List<T>, a: Stream<T>) -> Stream<T> {
+        Stream(Ptr::new(Lock::new(StreamCell::Rotate(f, r, a))))
+    }
+
+    fn force(&self) -> StreamNode<T> {
+        let node = match *lock(&self.0) {
+            StreamCell::Forced(ref n) => return n.clone(),
+            StreamCell::Rotate(ref f, ref r, ref a) =>
+                match (f.force(), r) {
+                    (StreamNode::Nil, &Cons(ref y, _)) =>
+                        StreamNode::Cons(y.clone(), a.clone()),
+                    (StreamNode::Cons(x, f1), &Cons(ref y, ref r1)) =>
+                        StreamNode::Cons(x, Stream::rotate(f1, (**r1).clone(), Stream::cons(y.clone(), a.clone()))),
+                    (_, &Nil) => panic!("rotate with an empty rear list")
+                }
+        };
+
+        *lock(&self.0) = StreamCell::Forced(node.clone());
+        node
+    }
+}
+
+// a fully evaluated front stream is as long as the queue, so uniquely owned cells are emptied and
+// their successors dropped from an explicit stack instead of recursively
+impl<T> Drop for Stream<T> {
+    fn drop(&mut self) {
+        fn take_next<T>(s: &mut Stream<T>, stack: &mut Vec<Stream<T>>) {
+            if let Some(c) = Ptr::get_mut(&mut s.0) {
+                match mem::replace(&mut *lock(c), StreamCell::Forced(StreamNode::Nil)) {
+                    StreamCell::Forced(StreamNode::Cons(_, s)) => stack.push(s),
+                    StreamCell::Forced(StreamNode::Nil) => (),
+                    StreamCell::Rotate(f, _, a) => {
+                        stack.push(f);
+                        stack.push(a);
+                    }
+                }
+            }
+        }
+
+        let mut stack = vec![];
+        take_next(self, &mut stack);
+
+        while let Some(mut s) = stack.pop() {
+            take_next(&mut s, &mut stack);
+        }
+    }
+}
+
+// Section 7.2: the front is a stream that is rotated with the rear list as soon as the rear gets
+// longer than it, the schedule points at the first unevaluated cell of the front and is advanced
+// by one on every operation, so no operation ever forces more than a single suspension
+#[derive(Clone, Debug)]
+pub struct RealTimeQueue<T> {
+    front: Stream<T>,
+    rear: List<T>,
+    schedule: Stream<T>,
+}
+
+impl<T: Clone> RealTimeQueue<T> {
+    fn exec(front: Stream<T>, rear: List<T>, schedule: Stream<T>) -> RealTimeQueue<T> {
+        match schedule.force() {
+            StreamNode::Cons(_, s) => RealTimeQueue { front: front, rear: rear, schedule: s },
+            StreamNode::Nil => {
+                let f = Stream::rotate(front, rear, Stream::nil());
+                RealTimeQueue { front: f.clone(), rear: Nil, schedule: f }
+            }
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for RealTimeQueue<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> RealTimeQueue<T> {
+        let mut q = RealTimeQueue::empty();
+        q.extend(iter);
+        q
+    }
+}
+
+impl<T: Clone> Extend<T> for RealTimeQueue<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = self.snoc(x);
+        }
+    }
+}
+
+impl<T: Clone> IntoIterator for RealTimeQueue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, RealTimeQueue<T>>;
+
+    fn into_iter(self) -> IntoIter<T, RealTimeQueue<T>> {
+        IntoIter { queue: self, marker: PhantomData }
+    }
+}
+
+impl<T: Clone> Queue<T> for RealTimeQueue<T> {
+    fn empty() -> RealTimeQueue<T> {
+        RealTimeQueue { front: Stream::nil(), rear: Nil, schedule: Stream::nil() }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self.front.force() {
+            StreamNode::Nil => true,
+            _ => false
+        }
+    }
+
+    fn snoc(&self, x: T) -> RealTimeQueue<T> {
+        RealTimeQueue::exec(self.front.clone(), self.rear.cons(x), self.schedule.clone())
+    }
+
+    fn try_head(&self) -> Result<T, EmptyError> {
+        match self.front.force() {
+            StreamNode::Cons(x, _) => Ok(x),
+            StreamNode::Nil => Err(EmptyError)
+        }
+    }
+
+    fn try_tail(&self) -> Result<RealTimeQueue<T>, EmptyError> {
+        match self.front.force() {
+            StreamNode::Cons(_, f) => Ok(RealTimeQueue::exec(f, self.rear.clone(), self.schedule.clone())),
+            StreamNode::Nil => Err(EmptyError)
+        }
+    }
+}
+
+// runs the same pseudo-random sequence of operations against `q` and a `VecDeque`, also checking
+// that every version produced along the way is left unchanged by later operations
+#[cfg(test)]
+fn check_against_vecdeque<Q: Queue<u32> + Clone>(q: Q) {
+    use std::collections::VecDeque;
+
+    use model::Rng;
+
+    fn drain<Q: Queue<u32> + Clone>(q: &Q) -> Vec<u32> {
+        let mut v = vec![];
+        let mut q = q.clone();
+
+        while !q.is_empty() {
+            v.push(q.head());
+            q = q.tail();
+        }
+
+        v
+    }
+
+    let mut rng = Rng(88172645);
+
+    let mut q = q;
+    let mut model = VecDeque::new();
+    let mut versions = vec![];
+
+    for i in 0..5000 {
+        let r = rng.next();
+
+        if r % 3 == 0 {
+            assert_eq!(q.try_tail().is_ok(), model.pop_front().is_some());
+            q = match q.try_tail() { Ok(t) => t, Err(_) => q };
+        } else {
+            q = q.snoc(r);
+            model.push_back(r);
+        }
+
+        assert_eq!(q.is_empty(), model.is_empty());
+        assert_eq!(q.try_head().ok(), model.front().cloned());
+
+        if i % 500 == 0 {
+            versions.push((q.clone(), model.clone()));
+        }
+    }
+
+    for (q, model) in versions {
+        assert_eq!(drain(&q), model.into_iter().collect::<Vec<u32>>());
+    }
+}
+
+#[test]
+fn batchedqueue() {
+    let q: BatchedQueue<usize> = Queue::empty();
+    let q2 = q.snoc(1).snoc(2).snoc(3);
+
+    assert!(q.is_empty());
+    assert!(!q2.is_empty());
+    assert_eq!(q.try_head(), Err(EmptyError));
+
+    assert_eq!(q2.head(), 1);
+    assert_eq!(q2.tail().head(), 2);
+    assert_eq!(q2.tail().snoc(4).tail().tail().head(), 4);
+    assert_eq!(q2.head(), 1);
+
+    let mut q3: BatchedQueue<usize> = vec![1, 2].into_iter().collect();
+    q3.extend(vec![3, 4]);
+    assert_eq!(q3.into_iter().collect::<Vec<usize>>(), vec![1, 2, 3, 4]);
+
+    check_against_vecdeque::<BatchedQueue<u32>>(Queue::empty());
+}
+
+#[test]
+fn realtimequeue() {
+    let q: RealTimeQueue<usize> = Queue::empty();
+    let q2 = q.snoc(1).snoc(2).snoc(3);
+
+    assert!(q.is_empty());
+    assert!(!q2.is_empty());
+    assert_eq!(q.try_head(), Err(EmptyError));
+
+    assert_eq!(q2.head(), 1);
+    assert_eq!(q2.tail().head(), 2);
+    assert_eq!(q2.tail().snoc(4).tail().tail().head(), 4);
+    assert_eq!(q2.head(), 1);
+
+    let mut q3: RealTimeQueue<usize> = vec![1, 2].into_iter().collect();
+    q3.extend(vec![3, 4]);
+    assert_eq!(q3.into_iter().collect::<Vec<usize>>(), vec![1, 2, 3, 4]);
+
+    check_against_vecdeque::<RealTimeQueue<u32>>(Queue::empty());
+}
+
+#[test]
+fn long_queues() {
+    let n = 1000000;
+
+    let mut q: BatchedQueue<usize> = Queue::empty();
+    let mut r: RealTimeQueue<usize> = Queue::empty();
+
+    for i in 0..n {
+        q = q.snoc(i);
+        r = r.snoc(i);
+    }
+
+    assert_eq!(q.head(), 0);
+    assert_eq!(r.head(), 0);
+
+    assert_eq!(q.clone().into_iter().count(), n);
+    assert_eq!(r.clone().into_iter().count(), n);
+}
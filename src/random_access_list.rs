@@ -0,0 +1,333 @@
+use std::fmt::{Display, Error, Formatter};
+use std::iter::FromIterator;
+
+use dot::Dot;
+use error::EmptyError;
+use ptr::Ptr;
+use stack::List::{Cons, Nil};
+use stack::{self, List, Stack};
+use tree_layout::LayoutTree;
+
+// `Stack::update` is overridden by implementors that support it in less than linear time
+pub trait RandomAccess<T>: Stack<T> {
+    fn get(&self, usize) -> Option<T>;
+
+    fn lookup(&self, i: usize) -> T {
+        match self.get(i) {
+            Some(x) => x,
+            None => panic!("index out of bounds")
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CompleteTree<T> {
+    Leaf(T),
+    Node(T, Ptr<CompleteTree<T>>, Ptr<CompleteTree<T>>),
+}
+
+use random_access_list::CompleteTree::{Leaf, Node};
+
+// Section 9.3.1: skew binary random-access lists. The list is a sequence of complete binary trees
+// together with their sizes, which are skew binary weights (2^k - 1) in increasing order where only
+// the two smallest may be equal. The elements of each tree are stored in preorder.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RandomAccessList<T> {
+    trees: List<(usize, Ptr<CompleteTree<T>>)>,
+}
+
+fn lookup_tree<T: Clone>(w: usize, i: usize, t: &CompleteTree<T>) -> T {
+    let (mut w, mut i, mut t) = (w, i, t);
+
+    loop {
+        match *t {
+            Leaf(ref x) => return x.clone(),
+            Node(ref x, _, _) if i == 0 => return x.clone(),
+            Node(_, ref t1, ref t2) => {
+                w = w / 2;
+
+                if i <= w {
+                    i = i - 1;
+                    t = t1;
+                } else {
+                    i = i - 1 - w;
+                    t = t2;
+                }
+            }
+        }
+    }
+}
+
+fn update_tree<T: Clone>(w: usize, i: usize, x: T, t: &CompleteTree<T>) -> CompleteTree<T> {
+    match *t {
+        Leaf(_) => Leaf(x),
+        Node(_, ref t1, ref t2) if i == 0 => Node(x, t1.clone(), t2.clone()),
+        Node(ref y, ref t1, ref t2) =>
+            if i <= w / 2 {
+                Node(y.clone(), Ptr::new(update_tree(w / 2, i - 1, x, t1)), t2.clone())
+            } else {
+                Node(y.clone(), t1.clone(), Ptr::new(update_tree(w / 2, i - 1 - w / 2, x, t2)))
+            }
+    }
+}
+
+impl<T: Clone> Stack<T> for RandomAccessList<T> {
+    fn empty() -> RandomAccessList<T> {
+        RandomAccessList { trees: Nil }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.trees.is_empty()
+    }
+
+    fn cons(&self, x: T) -> RandomAccessList<T> {
+        if let Cons((w1, ref t1), ref ts) = self.trees {
+            if let Cons((w2, ref t2), ref rest) = **ts {
+                if w1 == w2 {
+                    let t = Node(x, t1.clone(), t2.clone());
+                    return RandomAccessList { trees: rest.cons((1 + w1 + w2, Ptr::new(t))) };
+                }
+            }
+        }
+
+        RandomAccessList { trees: self.trees.cons((1, Ptr::new(Leaf(x)))) }
+    }
+
+    fn try_head(&self) -> Result<T, EmptyError> {
+        match self.trees {
+            Cons((_, ref t), _) =>
+                match **t {
+                    Leaf(ref x) | Node(ref x, _, _) => Ok(x.clone())
+                },
+            Nil => Err(EmptyError)
+        }
+    }
+
+    fn try_tail(&self) -> Result<RandomAccessList<T>, EmptyError> {
+        match self.trees {
+            Cons((w, ref t), ref ts) =>
+                match **t {
+                    Leaf(_) => Ok(RandomAccessList { trees: (**ts).clone() }),
+                    Node(_, ref t1, ref t2) =>
+                        Ok(RandomAccessList { trees: ts.cons((w / 2, t2.clone())).cons((w / 2, t1.clone())) })
+                },
+            Nil => Err(EmptyError)
+        }
+    }
+
+    fn update(&self, i: usize, x: T) -> RandomAccessList<T> {
+        let mut prefix = vec![];
+        let mut i = i;
+        let mut ts = &self.trees;
+
+        loop {
+            match *ts {
+                Cons((w, ref t), ref rest) =>
+                    if i < w {
+                        let l = rest.cons((w, Ptr::new(update_tree(w, i, x, t))));
+                        return RandomAccessList { trees: prefix.into_iter().rev().fold(l, |acc, e| acc.cons(e)) };
+                    } else {
+                        prefix.push((w, t.clone()));
+                        i = i - w;
+                        ts = rest;
+                    },
+                Nil => panic!("index out of bounds")
+            }
+        }
+    }
+}
+
+impl<T: Clone> RandomAccess<T> for RandomAccessList<T> {
+    fn get(&self, i: usize) -> Option<T> {
+        let mut i = i;
+
+        for &(w, ref t) in self.trees.iter() {
+            if i < w {
+                return Some(lookup_tree(w, i, t));
+            }
+
+            i = i - w;
+        }
+
+        None
+    }
+}
+
+// iterates over the elements from head to tail
+pub struct Iter<'a, T: 'a> {
+    trees: stack::Iter<'a, (usize, Ptr<CompleteTree<T>>)>,
+    stack: Vec<&'a CompleteTree<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.stack.is_empty() {
+            match self.trees.next() {
+                Some(&(_, ref t)) => self.stack.push(t),
+                None => return None
+            }
+        }
+
+        match self.stack.pop() {
+            Some(&Leaf(ref x)) => Some(x),
+            Some(&Node(ref x, ref t1, ref t2)) => {
+                self.stack.push(t2);
+                self.stack.push(t1);
+                Some(x)
+            },
+            None => None
+        }
+    }
+}
+
+impl<T> RandomAccessList<T> {
+    pub fn iter(&self) -> Iter<T> {
+        Iter { trees: self.trees.iter(), stack: vec![] }
+    }
+
+    // the complete trees of the list from the head on, to be laid out one at a time
+    pub fn trees(&self) -> Vec<&CompleteTree<T>> {
+        self.trees.iter().map(|&(_, ref t)| &**t).collect()
+    }
+}
+
+impl<T: Clone> LayoutTree for CompleteTree<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&CompleteTree<T>>)> {
+        match *self {
+            Leaf(ref x) => Some((x.clone(), vec![])),
+            Node(ref x, ref t1, ref t2) => Some((x.clone(), vec![&**t1, &**t2]))
+        }
+    }
+}
+
+impl<T: Clone> Dot for RandomAccessList<T> {
+    type Node = CompleteTree<T>;
+
+    fn dot_roots(&self) -> Vec<&CompleteTree<T>> {
+        self.trees()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RandomAccessList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// the first element of the iterator becomes the head of the list
+impl<T: Clone> FromIterator<T> for RandomAccessList<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> RandomAccessList<T> {
+        let xs: Vec<T> = iter.into_iter().collect();
+        xs.into_iter().rev().fold(Stack::empty(), |l: RandomAccessList<T>, x| l.cons(x))
+    }
+}
+
+// appends the elements at the end of the list, copying it
+impl<T: Clone> Extend<T> for RandomAccessList<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        let l: RandomAccessList<T> = iter.into_iter().collect();
+        *self = self.append(&l);
+    }
+}
+
+impl<T: Display> Display for RandomAccessList<T> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        try!(write!(f, "["));
+
+        for (i, x) in self.iter().enumerate() {
+            if i == 0 { try!(write!(f, "{}", x)); }
+            else { try!(write!(f, ", {}", x)); }
+        }
+
+        write!(f, "]")
+    }
+}
+
+#[test]
+fn random_access_list() {
+    let l1: RandomAccessList<usize> = Stack::empty();
+    let l2 = l1.cons(3).cons(2).cons(1);
+    let l3 = l1.cons(5).cons(4);
+
+    assert!(l1.is_empty());
+    assert!(!l2.is_empty());
+    assert_eq!(l2.head(), 1);
+    assert_eq!(l2.tail(), l1.cons(3).cons(2));
+    assert_eq!(l1.try_head(), Err(EmptyError));
+
+    assert_eq!(l2.lookup(0), 1);
+    assert_eq!(l2.lookup(2), 3);
+    assert_eq!(l2.get(3), None);
+
+    assert_eq!(l2.update(1, 0).iter().cloned().collect::<Vec<usize>>(), vec![1, 0, 3]);
+    assert_eq!(l2.append(&l3).iter().cloned().collect::<Vec<usize>>(), vec![1, 2, 3, 4, 5]);
+    assert_eq!((1..6).collect::<RandomAccessList<usize>>(), l2.append(&l3));
+    assert_eq!(format!("{}", l2), "[1, 2, 3]");
+}
+
+#[test]
+fn random_access_list_layout() {
+    use dot::to_dot;
+    use tree_layout::{absolute, design};
+
+    // four elements are a tree of one and one of three, in preorder
+    let l: RandomAccessList<usize> = (0..4).collect();
+    let ts = l.trees();
+    assert_eq!(ts.len(), 2);
+    assert_eq!(design(ts[0]).unwrap().label, 0);
+
+    let t = absolute(&design(ts[1]).unwrap());
+    assert_eq!((t.label, t.x), (1, 0.0));
+    assert_eq!(t.children.iter().map(|c| (c.label, c.x)).collect::<Vec<(usize, f64)>>(), vec![(2, -0.5), (3, 0.5)]);
+
+    assert_eq!(to_dot(&[("l", &l), ("tail", &l.tail())]).matches("[label=\"3\"]").count(), 1);
+}
+
+#[test]
+fn random_access_list_against_vec() {
+    use model::Rng;
+
+    let mut rng = Rng(521288629);
+
+    let mut l: RandomAccessList<u32> = Stack::empty();
+    let mut model: Vec<u32> = vec![];
+
+    for _ in 0..5000 {
+        let r = rng.next();
+
+        match r % 4 {
+            0 if !model.is_empty() => {
+                l = l.tail();
+                model.remove(0);
+            },
+            1 if !model.is_empty() => {
+                let i = (r as usize / 4) % model.len();
+                l = l.update(i, r);
+                model[i] = r;
+            },
+            _ => {
+                l = l.cons(r);
+                model.insert(0, r);
+            }
+        }
+
+        assert_eq!(l.is_empty(), model.is_empty());
+
+        if !model.is_empty() {
+            let i = (r as usize / 4) % model.len();
+            assert_eq!(l.lookup(i), model[i]);
+            assert_eq!(l.head(), model[0]);
+        }
+
+        assert_eq!(l.get(model.len()), None);
+    }
+
+    assert_eq!(l.iter().cloned().collect::<Vec<u32>>(), model);
+}
@@ -0,0 +1,775 @@
+use std::cmp::Ordering;
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::fmt::{Display, Error, Formatter};
+use std::iter::FromIterator;
+
+use dot::Dot;
+use map::Map;
+use ptr::Ptr;
+use set::{Keyed, Set};
+use tree_layout::LayoutTree;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+use red_black_tree::Color::{Black, Red};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RedBlackTree<T> {
+    Tip,
+    Node(Color, Ptr<RedBlackTree<T>>, T, Ptr<RedBlackTree<T>>),
+}
+
+use red_black_tree::RedBlackTree::{Node, Tip};
+
+// rewrites any black node with a red child and a red grandchild into a red node with two
+// black children (the four cases of Figure 3.5)
+fn balance<T: Clone>(c: Color, l: Ptr<RedBlackTree<T>>, x: T, r: Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    if c == Black {
+        if let Node(Red, ref ll, ref y, ref lr) = *l {
+            if let Node(Red, ref a, ref z, ref b) = **ll {
+                return Node(Red,
+                            Ptr::new(Node(Black, a.clone(), z.clone(), b.clone())),
+                            y.clone(),
+                            Ptr::new(Node(Black, lr.clone(), x, r.clone())));
+            }
+
+            if let Node(Red, ref b, ref z, ref d) = **lr {
+                return Node(Red,
+                            Ptr::new(Node(Black, ll.clone(), y.clone(), b.clone())),
+                            z.clone(),
+                            Ptr::new(Node(Black, d.clone(), x, r.clone())));
+            }
+        }
+
+        if let Node(Red, ref rl, ref y, ref rr) = *r {
+            if let Node(Red, ref b, ref z, ref d) = **rl {
+                return Node(Red,
+                            Ptr::new(Node(Black, l.clone(), x, b.clone())),
+                            z.clone(),
+                            Ptr::new(Node(Black, d.clone(), y.clone(), rr.clone())));
+            }
+
+            if let Node(Red, ref b, ref z, ref d) = **rr {
+                return Node(Red,
+                            Ptr::new(Node(Black, l.clone(), x, rl.clone())),
+                            y.clone(),
+                            Ptr::new(Node(Black, b.clone(), z.clone(), d.clone())));
+            }
+        }
+    }
+
+    Node(c, l, x, r)
+}
+
+// inserts `x` as a red leaf and rebalances on the way up; an element comparing `Equal` to an
+// existing one replaces it if `replace` is set, otherwise the original subtree is kept
+fn insert_by<T: Clone, F>(t: &RedBlackTree<T>, x: T, cmp: &F, replace: bool) -> RedBlackTree<T>
+    where F: Fn(&T, &T) -> Ordering {
+    fn ins<T: Clone, F>(t: &RedBlackTree<T>, x: T, cmp: &F, replace: bool) -> RedBlackTree<T>
+        where F: Fn(&T, &T) -> Ordering {
+        match *t {
+            Tip => Node(Red, Ptr::new(Tip), x, Ptr::new(Tip)),
+            Node(c, ref l, ref v, ref r) =>
+                match cmp(&x, v) {
+                    Less => balance(c, Ptr::new(ins(l, x, cmp, replace)), v.clone(), r.clone()),
+                    Greater => balance(c, l.clone(), v.clone(), Ptr::new(ins(r, x, cmp, replace))),
+                    Equal =>
+                        if replace { Node(c, l.clone(), x, r.clone()) } else { t.clone() }
+                }
+        }
+    }
+
+    match ins(t, x, cmp, replace) {
+        Node(_, l, v, r) => Node(Black, l, v, r),
+        Tip => panic!("insert returned an empty tree")
+    }
+}
+
+fn is_black<T>(t: &RedBlackTree<T>) -> bool {
+    match *t {
+        Node(Black, _, _, _) => true,
+        _ => false
+    }
+}
+
+fn paint<T: Clone>(c: Color, t: &RedBlackTree<T>) -> RedBlackTree<T> {
+    match *t {
+        Node(_, ref l, ref x, ref r) => Node(c, l.clone(), x.clone(), r.clone()),
+        Tip => Tip
+    }
+}
+
+// Deletion follows Kahrs' "Red-black trees with types" (2001), which Okasaki leaves as an
+// exercise. Deleting from a black subtree shortens its black height by one, `balance_left` and
+// `balance_right` restore the invariant when that happened on the left or right side.
+
+fn balance_left<T: Clone>(l: Ptr<RedBlackTree<T>>, x: T, r: Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    if let Node(Red, _, _, _) = *l {
+        return Node(Red, Ptr::new(paint(Black, &l)), x, r);
+    }
+
+    match *r {
+        Node(Black, _, _, _) =>
+            balance(Black, l, x, Ptr::new(paint(Red, &r))),
+        Node(Red, ref rl, ref y, ref rr) if is_black(rl) =>
+            match **rl {
+                Node(_, ref a, ref z, ref b) =>
+                    Node(Red,
+                         Ptr::new(Node(Black, l.clone(), x, a.clone())),
+                         z.clone(),
+                         Ptr::new(balance(Black, b.clone(), y.clone(), Ptr::new(paint(Red, rr))))),
+                Tip => panic!("unreachable")
+            },
+        _ => Node(Red, l.clone(), x, r.clone())
+    }
+}
+
+fn balance_right<T: Clone>(l: Ptr<RedBlackTree<T>>, x: T, r: Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    if let Node(Red, _, _, _) = *r {
+        return Node(Red, l, x, Ptr::new(paint(Black, &r)));
+    }
+
+    match *l {
+        Node(Black, _, _, _) =>
+            balance(Black, Ptr::new(paint(Red, &l)), x, r),
+        Node(Red, ref ll, ref y, ref lr) if is_black(lr) =>
+            match **lr {
+                Node(_, ref a, ref z, ref b) =>
+                    Node(Red,
+                         Ptr::new(balance(Black, Ptr::new(paint(Red, ll)), y.clone(), a.clone())),
+                         z.clone(),
+                         Ptr::new(Node(Black, b.clone(), x, r.clone()))),
+                Tip => panic!("unreachable")
+            },
+        _ => Node(Red, l.clone(), x, r.clone())
+    }
+}
+
+// joins the two children of a deleted node
+fn combine<T: Clone>(l: &Ptr<RedBlackTree<T>>, r: &Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    match (&**l, &**r) {
+        (&Tip, _) => (**r).clone(),
+        (_, &Tip) => (**l).clone(),
+        (&Node(Red, ref a, ref x, ref b), &Node(Red, ref c, ref y, ref d)) =>
+            match combine(b, c) {
+                Node(Red, b1, z, c1) =>
+                    Node(Red,
+                         Ptr::new(Node(Red, a.clone(), x.clone(), b1)),
+                         z,
+                         Ptr::new(Node(Red, c1, y.clone(), d.clone()))),
+                bc => Node(Red, a.clone(), x.clone(), Ptr::new(Node(Red, Ptr::new(bc), y.clone(), d.clone())))
+            },
+        (&Node(Black, ref a, ref x, ref b), &Node(Black, ref c, ref y, ref d)) =>
+            match combine(b, c) {
+                Node(Red, b1, z, c1) =>
+                    Node(Red,
+                         Ptr::new(Node(Black, a.clone(), x.clone(), b1)),
+                         z,
+                         Ptr::new(Node(Black, c1, y.clone(), d.clone()))),
+                bc => balance_left(a.clone(), x.clone(), Ptr::new(Node(Black, Ptr::new(bc), y.clone(), d.clone())))
+            },
+        (_, &Node(Red, ref b, ref x, ref c)) =>
+            Node(Red, Ptr::new(combine(l, b)), x.clone(), c.clone()),
+        (&Node(Red, ref a, ref x, ref b), _) =>
+            Node(Red, a.clone(), x.clone(), Ptr::new(combine(b, r)))
+    }
+}
+
+// removes the element for which `cmp` returns `Equal`, `cmp` compares the element being looked
+// for against the one stored in each node
+fn delete_by<T: Clone, F>(t: &RedBlackTree<T>, cmp: &F) -> RedBlackTree<T>
+    where F: Fn(&T) -> Ordering {
+    fn del<T: Clone, F>(t: &RedBlackTree<T>, cmp: &F) -> RedBlackTree<T>
+        where F: Fn(&T) -> Ordering {
+        match *t {
+            Tip => Tip,
+            Node(_, ref l, ref v, ref r) =>
+                match cmp(v) {
+                    Less =>
+                        if is_black(l) { balance_left(Ptr::new(del(l, cmp)), v.clone(), r.clone()) }
+                        else { Node(Red, Ptr::new(del(l, cmp)), v.clone(), r.clone()) },
+                    Greater =>
+                        if is_black(r) { balance_right(l.clone(), v.clone(), Ptr::new(del(r, cmp))) }
+                        else { Node(Red, l.clone(), v.clone(), Ptr::new(del(r, cmp))) },
+                    Equal => combine(l, r)
+                }
+        }
+    }
+
+    paint(Black, &del(t, cmp))
+}
+
+// The set operations follow Blelloch, Ferizovic and Sun's "Just join for parallel ordered sets"
+// (2016). `join` builds a red-black tree out of two trees and an element between them in time
+// proportional to the difference of their black heights, so `split` and the set operations can
+// work like those of `Tree` while keeping the results balanced.
+
+fn is_red<T>(t: &RedBlackTree<T>) -> bool {
+    match *t {
+        Node(Red, _, _, _) => true,
+        _ => false
+    }
+}
+
+// the number of black nodes on any path from the root to a leaf
+fn black_height<T>(t: &RedBlackTree<T>) -> usize {
+    let mut t = t;
+    let mut h = 0;
+
+    while let Node(c, ref l, _, _) = *t {
+        if c == Black {
+            h = h + 1;
+        }
+
+        t = l;
+    }
+
+    h
+}
+
+// joins `l`, `x` and `r` where `l`, of black height `hl`, is at least as high as `r`, hanging `r`
+// off the right spine of `l` at the first black node of the same height; a red node with a red
+// right child is rotated away at the black node above it, or left at the root for `join` to fix
+fn join_right<T: Clone>(l: &Ptr<RedBlackTree<T>>, hl: usize, x: T, r: &Ptr<RedBlackTree<T>>, hr: usize) -> RedBlackTree<T> {
+    match **l {
+        Node(c, ref a, ref y, ref b) if hl > hr || c == Red => {
+            let t = join_right(b, if c == Black { hl - 1 } else { hl }, x, r, hr);
+
+            match t {
+                Node(Red, ref t1, ref z, ref t2) if c == Black && is_red(t2) =>
+                    Node(Red, Ptr::new(Node(Black, a.clone(), y.clone(), t1.clone())), z.clone(), Ptr::new(paint(Black, t2))),
+                _ => Node(c, a.clone(), y.clone(), Ptr::new(t))
+            }
+        },
+        _ => Node(Red, l.clone(), x, r.clone())
+    }
+}
+
+// the mirror image of `join_right`, for an `r` at least as high as `l`
+fn join_left<T: Clone>(l: &Ptr<RedBlackTree<T>>, hl: usize, x: T, r: &Ptr<RedBlackTree<T>>, hr: usize) -> RedBlackTree<T> {
+    match **r {
+        Node(c, ref a, ref y, ref b) if hr > hl || c == Red => {
+            let t = join_left(l, hl, x, a, if c == Black { hr - 1 } else { hr });
+
+            match t {
+                Node(Red, ref t1, ref z, ref t2) if c == Black && is_red(t1) =>
+                    Node(Red, Ptr::new(paint(Black, t1)), z.clone(), Ptr::new(Node(Black, t2.clone(), y.clone(), b.clone()))),
+                _ => Node(c, Ptr::new(t), y.clone(), b.clone())
+            }
+        },
+        _ => Node(Red, l.clone(), x, r.clone())
+    }
+}
+
+// joins two trees and an element that is bigger than every element of `l` and smaller than every
+// element of `r`, sharing every subtree of either tree off the spine it goes down
+fn join<T: Clone>(l: &Ptr<RedBlackTree<T>>, x: T, r: &Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    let (hl, hr) = (black_height(l), black_height(r));
+
+    if hl > hr {
+        match join_right(l, hl, x, r, hr) {
+            Node(Red, ref a, ref y, ref b) if is_red(b) => Node(Black, a.clone(), y.clone(), b.clone()),
+            t => t
+        }
+    } else if hr > hl {
+        match join_left(l, hl, x, r, hr) {
+            Node(Red, ref a, ref y, ref b) if is_red(a) => Node(Black, a.clone(), y.clone(), b.clone()),
+            t => t
+        }
+    } else if is_red(l) || is_red(r) {
+        Node(Black, l.clone(), x, r.clone())
+    } else {
+        Node(Red, l.clone(), x, r.clone())
+    }
+}
+
+// joins two trees where every element of `l` is smaller than every element of `r`, through the
+// biggest element of `l`
+fn glue<T: Ord + Clone>(l: &Ptr<RedBlackTree<T>>, r: &Ptr<RedBlackTree<T>>) -> RedBlackTree<T> {
+    let mut t = &**l;
+
+    while let Node(_, _, ref x, ref right) = *t {
+        if let Tip = **right {
+            let l1 = delete_by(l, &|v: &T| x.cmp(v));
+            return join(&Ptr::new(l1), x.clone(), r);
+        }
+
+        t = right;
+    }
+
+    (**r).clone()
+}
+
+impl<T: Ord + Clone> Set<T> for RedBlackTree<T> {
+    fn empty() -> RedBlackTree<T> {
+        Tip
+    }
+
+    fn insert(&self, x: T) -> RedBlackTree<T> {
+        insert_by(self, x, &|a: &T, b: &T| a.cmp(b), false)
+    }
+
+    fn member(&self, x: T) -> bool {
+        match *self {
+            Tip => false,
+            Node(_, ref l, ref v, _) if x < *v => l.member(x),
+            Node(_, _, ref v, ref r) if x > *v => r.member(x),
+            _ => true
+        }
+    }
+
+    fn delete(&self, x: T) -> RedBlackTree<T> {
+        // deletion recolors the whole search path even when the element is missing, so keep the
+        // original tree in that case
+        if self.member(x.clone()) { delete_by(self, &|v: &T| x.cmp(v)) } else { self.clone() }
+    }
+
+    // like those of `Tree`, these split the second tree around the root of the first one and
+    // recurse on both halves, which takes O(m log(n/m + 1)) time for sizes m <= n
+    fn union(&self, t: &RedBlackTree<T>) -> RedBlackTree<T> {
+        match (self, t) {
+            (_, &Tip) => self.clone(),
+            (&Tip, _) => t.clone(),
+            (&Node(_, ref l1, ref x, ref r1), _) => {
+                let (l2, _, r2) = t.split(x);
+                join(&Ptr::new(l1.union(&l2)), x.clone(), &Ptr::new(r1.union(&r2)))
+            }
+        }
+    }
+
+    fn intersection(&self, t: &RedBlackTree<T>) -> RedBlackTree<T> {
+        match (self, t) {
+            (_, &Tip) | (&Tip, _) => Tip,
+            (&Node(_, ref l1, ref x, ref r1), _) => {
+                let (l2, found, r2) = t.split(x);
+                let l = Ptr::new(l1.intersection(&l2));
+                let r = Ptr::new(r1.intersection(&r2));
+
+                if found { join(&l, x.clone(), &r) } else { glue(&l, &r) }
+            }
+        }
+    }
+
+    fn difference(&self, t: &RedBlackTree<T>) -> RedBlackTree<T> {
+        match (self, t) {
+            (_, &Tip) => self.clone(),
+            (&Tip, _) => Tip,
+            (_, &Node(_, ref l2, ref x, ref r2)) => {
+                let (l1, _, r1) = self.split(x);
+                glue(&Ptr::new(l1.difference(l2)), &Ptr::new(r1.difference(r2)))
+            }
+        }
+    }
+
+    fn is_subset(&self, t: &RedBlackTree<T>) -> bool {
+        self.iter().all(|x| t.member(x.clone()))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Map<K, V> for RedBlackTree<(K, V)> {
+    fn empty() -> RedBlackTree<(K, V)> {
+        Tip
+    }
+
+    fn bind(&self, k: K, v: V) -> RedBlackTree<(K, V)> {
+        insert_by(self, (k, v), &|a: &(K, V), b: &(K, V)| a.0.cmp(&b.0), true)
+    }
+
+    fn get(&self, x: K) -> Option<V> {
+        match *self {
+            Tip => None,
+            Node(_, ref l, (ref k, _), _) if x < *k => l.get(x),
+            Node(_, _, (ref k, _), ref r) if x > *k => r.get(x),
+            Node(_, _, (_, ref v), _) => Some(v.clone())
+        }
+    }
+
+    fn unbind(&self, x: K) -> RedBlackTree<(K, V)> {
+        if self.get(x.clone()).is_some() { delete_by(self, &|e: &(K, V)| x.cmp(&e.0)) } else { self.clone() }
+    }
+}
+
+// iterates over the elements in order
+pub struct Iter<'a, T: 'a> {
+    stack: Vec<&'a RedBlackTree<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, t: &'a RedBlackTree<T>) {
+        let mut t = t;
+
+        while let Node(_, ref l, _, _) = *t {
+            self.stack.push(t);
+            t = l;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.stack.pop() {
+            Some(&Node(_, _, ref x, ref r)) => {
+                self.push_left(r);
+                Some(x)
+            },
+            _ => None
+        }
+    }
+}
+
+impl<T> RedBlackTree<T> {
+    pub fn iter(&self) -> Iter<T> {
+        let mut it = Iter { stack: vec![] };
+        it.push_left(self);
+        it
+    }
+}
+
+impl<T: Ord> RedBlackTree<T> {
+    // builds a tree out of elements given in strictly increasing order in linear time, like
+    // `Tree::from_sorted_iter`, which checks the order the same way; every level that is full is
+    // black and the nodes of the last, partially filled one are red
+    pub fn from_sorted_iter<I: IntoIterator<Item=T>>(iter: I) -> RedBlackTree<T> {
+        fn build<T, I: Iterator<Item=T>>(n: usize, depth: usize, red: usize, iter: &mut I) -> RedBlackTree<T> {
+            if n == 0 {
+                return Tip;
+            }
+
+            let l = build(n / 2, depth + 1, red, iter);
+            let x = iter.next().unwrap();
+            let r = build(n - n / 2 - 1, depth + 1, red, iter);
+
+            Node(if depth == red { Red } else { Black }, Ptr::new(l), x, Ptr::new(r))
+        }
+
+        let xs: Vec<T> = iter.into_iter().collect();
+        debug_assert!(xs.windows(2).all(|w| w[0] < w[1]), "elements are not strictly increasing");
+
+        let n = xs.len();
+
+        // the number of full levels, floor(log2(n + 1))
+        let mut full = 0;
+        while (1 << (full + 1)) - 1 <= n {
+            full = full + 1;
+        }
+
+        build(n, 0, full, &mut xs.into_iter())
+    }
+}
+
+impl<T: Ord + Clone> RedBlackTree<T> {
+    // splits the tree into the elements smaller and bigger than `x`, and whether `x` was in it,
+    // joining the subtrees that hang off the search path back together on the way up
+    pub fn split(&self, x: &T) -> (RedBlackTree<T>, bool, RedBlackTree<T>) {
+        match *self {
+            Tip => (Tip, false, Tip),
+            Node(_, ref l, ref v, ref r) =>
+                match x.cmp(v) {
+                    Less => {
+                        let (ll, found, lr) = l.split(x);
+                        (ll, found, join(&Ptr::new(lr), v.clone(), r))
+                    },
+                    Greater => {
+                        let (rl, found, rr) = r.split(x);
+                        (join(l, v.clone(), &Ptr::new(rl)), found, rr)
+                    },
+                    Equal => ((**l).clone(), true, (**r).clone())
+                }
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RedBlackTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// like `Tree`, collecting binds every element by its key
+impl<T: Keyed + Clone> FromIterator<T> for RedBlackTree<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> RedBlackTree<T> {
+        let mut t = Tip;
+        t.extend(iter);
+        t
+    }
+}
+
+impl<T: Keyed + Clone> Extend<T> for RedBlackTree<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = insert_by(self, x, &|a: &T, b: &T| a.cmp_key(b), true);
+        }
+    }
+}
+
+impl<T: Display> Display for RedBlackTree<T> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        fn color(c: Color) -> &'static str {
+            match c {
+                Red => "R",
+                Black => "B"
+            }
+        }
+
+        fn aux<T: Display>(f: &mut Formatter, t: &RedBlackTree<T>, right: bool, indent: &str) -> Result<(), Error> {
+            match *t {
+                Node(c, ref l, ref x, ref r) => {
+                    try!(aux(f, r, true, &(indent.to_string() + if right { "        " } else { " |      " })));
+
+                    try!(write!(f, "{}", indent));
+                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
+                    try!(write!(f, "{}", "----- "));
+
+                    try!(writeln!(f, "({}, {})", color(c), x));
+
+                    aux(f, l, false, &(indent.to_string() + if right { " |      " } else { "        " }))
+                },
+                Tip => {
+                    try!(write!(f, "{}", indent));
+                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
+                    try!(write!(f, "{}", "----- "));
+
+                    writeln!(f, "{}", "()")
+                }
+            }
+        }
+
+        match *self {
+            Node(c, ref l, ref x, ref r) => {
+                try!(aux(f, r, true, ""));
+                try!(writeln!(f, "({}, {})", color(c), x));
+                aux(f, l, false, "")
+            },
+            Tip => Result::Ok(())
+        }
+    }
+}
+
+// the nodes are labelled with their elements only, their colors are left out
+impl<T: Clone> LayoutTree for RedBlackTree<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&RedBlackTree<T>>)> {
+        match *self {
+            Node(_, ref l, ref x, ref r) => Some((x.clone(), vec![&**l, &**r])),
+            Tip => None
+        }
+    }
+}
+
+impl<T: Clone> Dot for RedBlackTree<T> {
+    type Node = RedBlackTree<T>;
+
+    fn dot_roots(&self) -> Vec<&RedBlackTree<T>> {
+        vec![self]
+    }
+}
+
+// checks the red-black invariants (no red node has a red child, every path from the root to a
+// leaf contains the same number of black nodes) plus the search tree ordering, returning the
+// black height of the tree
+#[cfg(test)]
+pub(crate) fn check_invariants<T: Ord>(t: &RedBlackTree<T>, lo: Option<&T>, hi: Option<&T>) -> usize {
+    match *t {
+        Tip => 1,
+        Node(c, ref l, ref x, ref r) => {
+            if let Some(lo) = lo { assert!(lo < x); }
+            if let Some(hi) = hi { assert!(x < hi); }
+
+            if c == Red {
+                match (&**l, &**r) {
+                    (&Node(Red, _, _, _), _) | (_, &Node(Red, _, _, _)) => panic!("red node with red child"),
+                    _ => ()
+                }
+            }
+
+            let bl = check_invariants(l, lo, Some(x));
+            let br = check_invariants(r, Some(x), hi);
+            assert_eq!(bl, br);
+
+            if c == Black { bl + 1 } else { bl }
+        }
+    }
+}
+
+#[test]
+fn redblacktree_set() {
+    let t: RedBlackTree<usize> = Set::empty();
+    let t2 = t.insert(1).insert(2).insert(3).insert(4).insert(5).insert(6).insert(7);
+
+    assert!(t2.member(1));
+    assert!(t2.member(4));
+    assert!(t2.member(7));
+
+    assert!(!t2.member(0));
+    assert!(!t2.member(8));
+
+    match t2 {
+        Node(c, _, _, _) => assert_eq!(c, Black),
+        Tip => panic!("empty tree")
+    }
+
+    check_invariants(&t2, None, None);
+
+    assert_eq!(t2.iter().cloned().collect::<Vec<usize>>(), vec![1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!((1..8).collect::<RedBlackTree<usize>>(), t2);
+}
+
+#[test]
+fn redblacktree_map() {
+    let m: RedBlackTree<(&str, usize)> = Map::empty();
+    let m2 = m.bind("hello", 0)
+        .bind("world", 1)
+        .bind("foo", 2)
+        .bind("bar", 3);
+
+    assert_eq!(m2.lookup("hello"), 0);
+    assert_eq!(m2.lookup("world"), 1);
+    assert_eq!(m2.lookup("foo"), 2);
+    assert_eq!(m2.lookup("bar"), 3);
+    assert_eq!(m2.get("baz"), None);
+
+    assert_eq!(m2.bind("foo", 4).lookup("foo"), 4);
+
+    let m3 = m2.unbind("hello").unbind("foo");
+
+    assert_eq!(m3.get("hello"), None);
+    assert_eq!(m3.get("foo"), None);
+    assert_eq!(m3.lookup("world"), 1);
+    assert_eq!(m3.lookup("bar"), 3);
+    assert_eq!(m2.unbind("baz"), m2);
+
+    ::map::check_entry_api::<RedBlackTree<(String, usize)>>();
+
+    let mut m4: RedBlackTree<(&str, usize)> = vec![("foo", 1), ("bar", 2)].into_iter().collect();
+    m4.extend(vec![("foo", 3), ("bar", 2)]);
+    check_invariants(&m4, None, None);
+    assert_eq!(m4.iter().cloned().collect::<Vec<(&str, usize)>>(), vec![("bar", 2), ("foo", 3)]);
+}
+
+#[test]
+fn redblacktree_invariants() {
+    use std::collections::BTreeSet;
+
+    use model::Rng;
+
+    let mut rng = Rng(2463534242);
+
+    let mut t: RedBlackTree<u32> = Set::empty();
+    let mut xs = vec![];
+
+    for _ in 0..2000 {
+        let x = rng.next() % 5000;
+        t = t.insert(x);
+        xs.push(x);
+    }
+
+    check_invariants(&t, None, None);
+
+    for x in xs.iter() {
+        assert!(t.member(*x));
+    }
+
+    // delete every other inserted element, checking the invariants as we go
+    let mut d = t.clone();
+    let mut model: BTreeSet<u32> = xs.iter().cloned().collect();
+
+    for (i, x) in xs.iter().enumerate().filter(|e| e.0 % 2 == 0) {
+        d = d.delete(*x);
+        model.remove(x);
+        assert!(!d.member(*x));
+
+        if i % 100 == 0 {
+            check_invariants(&d, None, None);
+        }
+    }
+
+    check_invariants(&d, None, None);
+
+    for x in xs.iter() {
+        assert_eq!(d.member(*x), model.contains(x));
+    }
+
+    // the set operations against the model, on the odd half of the inserted elements
+    let o: RedBlackTree<u32> = xs.iter().enumerate().filter(|e| e.0 % 2 == 1).map(|e| *e.1).collect();
+    let odd: BTreeSet<u32> = o.iter().cloned().collect();
+    let all: BTreeSet<u32> = xs.iter().cloned().collect();
+
+    let u = d.union(&o);
+    check_invariants(&u, None, None);
+    assert_eq!(u.iter().cloned().collect::<Vec<u32>>(), model.union(&odd).cloned().collect::<Vec<u32>>());
+
+    let i = t.intersection(&o);
+    check_invariants(&i, None, None);
+    assert_eq!(i.iter().cloned().collect::<Vec<u32>>(), odd.iter().cloned().collect::<Vec<u32>>());
+
+    let m = t.difference(&o);
+    check_invariants(&m, None, None);
+    assert_eq!(m.iter().cloned().collect::<Vec<u32>>(), all.difference(&odd).cloned().collect::<Vec<u32>>());
+
+    assert!(d.is_subset(&t));
+    assert!(!t.is_subset(&d));
+
+    for n in 0..300 {
+        let f = RedBlackTree::from_sorted_iter(0..n);
+        check_invariants(&f, None, None);
+        assert_eq!(f.iter().cloned().collect::<Vec<u32>>(), (0..n).collect::<Vec<u32>>());
+        check_invariants(&f.insert(n), None, None);
+        check_invariants(&f.delete(n / 2), None, None);
+    }
+
+    let f = RedBlackTree::from_sorted_iter(xs.iter().cloned().collect::<BTreeSet<u32>>());
+    check_invariants(&f, None, None);
+    assert_eq!(f.iter().collect::<Vec<&u32>>(), t.iter().collect::<Vec<&u32>>());
+
+    // sorted input is the degenerate case for the unbalanced tree
+    let mut s: RedBlackTree<u32> = Set::empty();
+    for x in 0..2000 {
+        s = s.insert(x);
+    }
+
+    // a red-black tree with n nodes has black height of at most log(n + 1) + 1
+    assert!(check_invariants(&s, None, None) <= 12);
+}
+
+// splitting and the set operations on trees of very different sizes, which joins trees of very
+// different black heights
+#[test]
+fn redblacktree_split() {
+    let t: RedBlackTree<u32> = (0..1000).map(|x| 2 * x).collect();
+
+    for x in (0..2001).filter(|x| x % 37 == 0) {
+        let (l, found, r) = t.split(&x);
+        check_invariants(&l, None, None);
+        check_invariants(&r, None, None);
+
+        assert_eq!(found, x % 2 == 0);
+        assert_eq!(l.iter().cloned().collect::<Vec<u32>>(), (0..1000).map(|y| 2 * y).filter(|y| *y < x).collect::<Vec<u32>>());
+        assert_eq!(r.iter().cloned().collect::<Vec<u32>>(), (0..1000).map(|y| 2 * y).filter(|y| *y > x).collect::<Vec<u32>>());
+    }
+
+    let small: RedBlackTree<u32> = vec![1, 998, 999, 1998, 5000].into_iter().collect();
+
+    for &(ref a, ref b) in &[(&t, &small), (&small, &t)] {
+        let u = a.union(b);
+        check_invariants(&u, None, None);
+        assert_eq!(u.iter().count(), 1003);
+
+        let i = a.intersection(b);
+        check_invariants(&i, None, None);
+        assert_eq!(i.iter().cloned().collect::<Vec<u32>>(), vec![998, 1998]);
+
+        let d = a.difference(b);
+        check_invariants(&d, None, None);
+        assert_eq!(d.iter().count(), if a.member(0) { 998 } else { 3 });
+    }
+}
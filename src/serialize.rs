@@ -0,0 +1,259 @@
+// Serde support, behind the `serde` feature. Every structure is written out in terms of its
+// elements and not of its nodes: stacks, queues, sets and heaps as sequences and the string trie
+// as a map. Deserializing rebuilds the structure through its own operations, so that the result
+// keeps its invariants whatever the input looks like: duplicates in a set are dropped and heaps
+// don't need their elements in order. A tree of pairs is read as a map, so two pairs with the same
+// key and different values are an error rather than two bindings of one key.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use heap::{BinomialHeap, Heap, LeftistHeap, PairingHeap, SkewBinomialHeap, SplayHeap};
+use queue::{BatchedQueue, RealTimeQueue};
+use random_access_list::RandomAccessList;
+use red_black_tree::RedBlackTree;
+use set::Keyed;
+use stack::List;
+use tree::Tree;
+use trie::{ByteTrie, PatriciaTrie};
+
+// sorts the elements and drops the duplicates, for the sets to be built in linear time, failing on
+// two bindings of the same key
+fn sorted<T: Keyed, E: Error>(xs: Vec<T>) -> Result<Vec<T>, E> {
+    let mut xs = xs;
+    xs.sort();
+    xs.dedup();
+
+    if xs.windows(2).any(|w| w[0].cmp_key(&w[1]) == Ordering::Equal) {
+        return Err(E::custom("duplicate key"));
+    }
+
+    Ok(xs)
+}
+
+// stacks, from the head to the bottom
+
+impl<T: Serialize> Serialize for List<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for List<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<List<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(xs.into_iter().collect())
+    }
+}
+
+impl<T: Serialize> Serialize for RandomAccessList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for RandomAccessList<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RandomAccessList<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(xs.into_iter().collect())
+    }
+}
+
+// queues, from the front to the rear
+
+impl<T: Serialize + Clone> Serialize for BatchedQueue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.clone())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for BatchedQueue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BatchedQueue<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(xs.into_iter().collect())
+    }
+}
+
+impl<T: Serialize + Clone> Serialize for RealTimeQueue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.clone())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for RealTimeQueue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RealTimeQueue<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(xs.into_iter().collect())
+    }
+}
+
+// sets in increasing order, maps as sequences of (key, value) pairs in increasing order of the keys
+
+impl<T: Serialize> Serialize for Tree<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Keyed> Deserialize<'de> for Tree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Tree<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(Tree::from_sorted_iter(try!(sorted(xs))))
+    }
+}
+
+impl<T: Serialize> Serialize for RedBlackTree<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Keyed> Deserialize<'de> for RedBlackTree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RedBlackTree<T>, D::Error> {
+        let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+        Ok(RedBlackTree::from_sorted_iter(try!(sorted(xs))))
+    }
+}
+
+// heaps in increasing order
+
+macro_rules! serialize_heap {
+    ($($heap: ident),*) => {$(
+        impl<T: Serialize + Ord + Clone> Serialize for $heap<T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.clone())
+            }
+        }
+
+        impl<'de, T: Deserialize<'de> + Ord + Clone> Deserialize<'de> for $heap<T> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$heap<T>, D::Error> {
+                let xs: Vec<T> = try!(Deserialize::deserialize(deserializer));
+                Ok(xs.into_iter().fold(Heap::empty(), |h: $heap<T>, x| h.insert(x)))
+            }
+        }
+    )*}
+}
+
+serialize_heap!(LeftistHeap, BinomialHeap, SkewBinomialHeap, PairingHeap, SplayHeap);
+
+// tries in lexicographic order of the keys
+
+impl<T: Serialize> Serialize for PatriciaTrie<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for PatriciaTrie<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PatriciaTrie<T>, D::Error> {
+        let m: BTreeMap<String, T> = try!(Deserialize::deserialize(deserializer));
+        Ok(m.into_iter().collect())
+    }
+}
+
+// byte strings aren't valid map keys in formats like JSON, so this one is a sequence of pairs and
+// a key that shows up more than once keeps its last value
+impl<T: Serialize> Serialize for ByteTrie<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for ByteTrie<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ByteTrie<T>, D::Error> {
+        let xs: Vec<(Vec<u8>, T)> = try!(Deserialize::deserialize(deserializer));
+        Ok(xs.into_iter().collect())
+    }
+}
+
+#[test]
+fn serialize_stacks_and_queues() {
+    use queue::Queue;
+    use serde_json::{from_str, to_string};
+    use stack::Stack;
+
+    let l: List<usize> = vec![1, 2, 3].into_iter().collect();
+    assert_eq!(to_string(&l).unwrap(), "[1,2,3]");
+    assert_eq!(from_str::<List<usize>>("[1,2,3]").unwrap(), l);
+
+    let r: RandomAccessList<usize> = (0..10).collect();
+    let r2: RandomAccessList<usize> = from_str(&to_string(&r).unwrap()).unwrap();
+    assert_eq!(r2.iter().collect::<Vec<&usize>>(), r.iter().collect::<Vec<&usize>>());
+    assert_eq!(r2.tail().head(), 1);
+
+    let q: BatchedQueue<usize> = BatchedQueue::empty().snoc(1).snoc(2).snoc(3).tail().snoc(4);
+    assert_eq!(to_string(&q).unwrap(), "[2,3,4]");
+    assert_eq!(from_str::<BatchedQueue<usize>>("[2,3,4]").unwrap().into_iter().collect::<Vec<usize>>(), vec![2, 3, 4]);
+
+    let q: RealTimeQueue<usize> = (0..5).collect();
+    assert_eq!(to_string(&q.tail()).unwrap(), "[1,2,3,4]");
+    assert_eq!(from_str::<RealTimeQueue<usize>>("[1,2]").unwrap().snoc(3).into_iter().collect::<Vec<usize>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn serialize_sets_and_maps() {
+    use map::Map;
+    use red_black_tree;
+    use serde_json::{from_str, to_string};
+    use set::Set;
+
+    let t: Tree<usize> = vec![5, 1, 4, 2, 3].into_iter().collect();
+    assert_eq!(to_string(&t).unwrap(), "[1,2,3,4,5]");
+
+    // out of order elements and duplicates still give a valid, balanced tree
+    let t2: Tree<usize> = from_str("[3,1,2,3,5,4,1]").unwrap();
+    assert_eq!(t2, Tree::from_sorted_iter(1..6));
+    assert!(t2.member(4));
+
+    let m: Tree<(String, usize)> = Map::empty();
+    let m = m.bind("b".to_string(), 2).bind("a".to_string(), 1);
+    assert_eq!(to_string(&m).unwrap(), r#"[["a",1],["b",2]]"#);
+    assert_eq!(from_str::<Tree<(String, usize)>>(r#"[["b",2],["a",1]]"#).unwrap().lookup("b".to_string()), 2);
+
+    // two values for one key would be two bindings of it, but the same pair twice is one binding
+    let json = r#"[["a",1],["b",2],["a",3]]"#;
+    assert!(from_str::<Tree<(String, usize)>>(json).unwrap_err().to_string().starts_with("duplicate key"));
+    assert!(from_str::<RedBlackTree<(String, usize)>>(json).is_err());
+    let m: RedBlackTree<(String, usize)> = from_str(r#"[["a",1],["b",2],["a",1]]"#).unwrap();
+    red_black_tree::check_invariants(&m, None, None);
+    assert_eq!(m.unbind("a".to_string()).get("a".to_string()), None);
+
+    let rb: RedBlackTree<usize> = (0..100).collect();
+    let rb2: RedBlackTree<usize> = from_str(&to_string(&rb).unwrap()).unwrap();
+    assert_eq!(rb2.iter().collect::<Vec<&usize>>(), rb.iter().collect::<Vec<&usize>>());
+    assert!(rb2.insert(100).delete(50).member(100));
+
+    let p: PatriciaTrie<usize> = Map::empty();
+    let p = p.bind("to".to_string(), 1).bind("tea".to_string(), 2);
+    assert_eq!(to_string(&p).unwrap(), r#"{"tea":2,"to":1}"#);
+    let p2: PatriciaTrie<usize> = from_str(r#"{"to":1,"tea":2,"ten":3}"#).unwrap();
+    assert_eq!(p2.keys_with_prefix("te"), vec!["tea", "ten"]);
+
+    let b: ByteTrie<usize> = vec![(vec![1, 2], 3), (vec![1], 4)].into_iter().collect();
+    assert_eq!(to_string(&b).unwrap(), "[[[1],4],[[1,2],3]]");
+    let b2: ByteTrie<usize> = from_str("[[[1,2],3],[[1],4],[[],5],[[1],6]]").unwrap();
+    assert_eq!(b2.lookup(vec![1]), 6);
+    assert_eq!(b2.lookup(vec![]), 5);
+    assert_eq!(b2.iter().count(), 3);
+}
+
+#[test]
+fn serialize_heaps() {
+    use serde_json::{from_str, to_string};
+
+    fn check<H: Heap<usize> + Serialize + for<'de> Deserialize<'de> + IntoIterator<Item=usize>>() {
+        let h: H = from_str("[5,3,9,1,3]").unwrap();
+        assert_eq!(to_string(&h).unwrap(), "[1,3,3,5,9]");
+        assert_eq!(h.find_min(), 1);
+        assert_eq!(h.into_iter().collect::<Vec<usize>>(), vec![1, 3, 3, 5, 9]);
+    }
+
+    check::<LeftistHeap<usize>>();
+    check::<BinomialHeap<usize>>();
+    check::<SkewBinomialHeap<usize>>();
+    check::<PairingHeap<usize>>();
+    check::<SplayHeap<usize>>();
+}
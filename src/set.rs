@@ -1,12 +1,107 @@
-use std::rc::Rc;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops;
 
-use tree::Tree;
+use ptr::Ptr;
+use tree::{rebuild, Tree};
 use tree::Tree::{Node, Tip};
 
 pub trait Set<T> {
     fn empty() -> Self;
     fn insert(&self, T) -> Self;
     fn member(&self, T) -> bool;
+
+    // returns a version without the given element, or the original set if it is not a member
+    fn delete(&self, T) -> Self;
+
+    fn union(&self, &Self) -> Self;
+    fn intersection(&self, &Self) -> Self;
+    fn difference(&self, &Self) -> Self;
+    fn is_subset(&self, &Self) -> bool;
+}
+
+// `floor` and `ceiling` return the greatest element smaller than or equal to the given one and
+// the smallest one greater than or equal to it
+pub trait OrderedSet<T>: Set<T> {
+    fn min(&self) -> Option<T>;
+    fn max(&self) -> Option<T>;
+    fn floor(&self, &T) -> Option<T>;
+    fn ceiling(&self, &T) -> Option<T>;
+    fn range<'a>(&'a self, ops::Range<T>) -> Box<dyn Iterator<Item=&'a T> + 'a> where T: 'a;
+}
+
+// How the elements of a tree are told apart when it is built from a sequence of them. A pair is a
+// binding of the map view, so two pairs with the same key are two values for one binding; any
+// other element is compared as a whole, which a type gets from an empty impl.
+pub trait Keyed: Ord {
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+macro_rules! keyed {
+    ($($t: ty),*) => {$(
+        impl Keyed for $t {}
+    )*}
+}
+
+keyed!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, char, (), String);
+
+impl<'a> Keyed for &'a str {}
+
+impl<T: Ord> Keyed for Vec<T> {}
+
+impl<T: Ord> Keyed for Option<T> {}
+
+impl<K: Ord, V: Ord> Keyed for (K, V) {
+    fn cmp_key(&self, other: &(K, V)) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+// The turns taken by a walk down a tree, left or right, one bit per level. The first 128 are kept
+// inline, so that a walk that turns out not to need them doesn't allocate unless the tree is deeper
+// than that.
+struct Turns {
+    first: u128,
+    rest: Vec<bool>,
+    len: usize,
+}
+
+impl Turns {
+    fn new() -> Turns {
+        Turns { first: 0, rest: vec![], len: 0 }
+    }
+
+    fn push(&mut self, left: bool) {
+        if self.len < 128 {
+            self.first |= (left as u128) << self.len;
+        } else {
+            self.rest.push(left);
+        }
+
+        self.len += 1;
+    }
+
+    fn left(&self, i: usize) -> bool {
+        if i < 128 { self.first >> i & 1 == 1 } else { self.rest[i - 128] }
+    }
+
+    // the nodes met by following the turns down from `t`, each with the side it was left by
+    fn path<'a, T>(&self, t: &'a Tree<T>) -> Vec<(&'a Tree<T>, bool)> {
+        let mut path = Vec::with_capacity(self.len);
+        let mut t = t;
+
+        for i in 0..self.len {
+            if let Node(ref l, _, ref r) = *t {
+                let left = self.left(i);
+                path.push((t, left));
+                t = if left { l } else { r };
+            }
+        }
+
+        path
+    }
 }
 
 impl<T: Ord + Clone> Set<T> for Tree<T> {
@@ -14,21 +109,175 @@ impl<T: Ord + Clone> Set<T> for Tree<T> {
         Tip
     }
 
+    // Exercises 2.3 and 2.4:
+    // like `member2`, the walk down keeps the last element not greater than `x` as a candidate and
+    // only compares it to `x` at the bottom, in at most d + 1 comparisons. Finding `x` there plays
+    // the part of Okasaki's exception: the original tree is returned, sharing both subtrees, so
+    // that the only copy made is that of the root element. Otherwise the turns taken on the way
+    // down are followed again to collect the search path, which is copied with `rebuild`.
     fn insert(&self, x: T) -> Tree<T> {
-        match *self {
-            Tip => Node(Rc::new(Tip), x, Rc::new(Tip)),
-            Node(ref l, ref v, ref r) if x < *v => Node(Rc::new(l.insert(x)), v.clone(), r.clone()),
-            Node(ref l, ref v, ref r) if x > *v => Node(l.clone(), v.clone(), Rc::new(r.insert(x))),
-            _ => self.clone()
+        let mut turns = Turns::new();
+        let mut t = self;
+        let mut candidate = None;
+
+        while let Node(ref l, ref v, ref r) = *t {
+            let left = x < *v;
+            turns.push(left);
+
+            if left {
+                t = l;
+            } else {
+                candidate = Some(v);
+                t = r;
+            }
         }
+
+        if let Some(c) = candidate {
+            if x == *c {
+                return self.clone();
+            }
+        }
+
+        rebuild(turns.path(self), Node(Ptr::new(Tip), x, Ptr::new(Tip)))
     }
 
     fn member(&self, x: T) -> bool {
-        match *self {
-            Tip => false,
-            Node(ref l, ref v, _) if x < *v => l.member(x),
-            Node(_, ref v, ref r) if x > *v => r.member(x),
-            _ => true
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return false,
+                Node(ref l, ref v, _) if x < *v => t = l,
+                Node(_, ref v, ref r) if x > *v => t = r,
+                _ => return true
+            }
+        }
+    }
+
+    fn delete(&self, x: T) -> Tree<T> {
+        let mut path = vec![];
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return self.clone(),
+                Node(ref l, ref v, _) if x < *v => { path.push((t, true)); t = l; },
+                Node(_, ref v, ref r) if x > *v => { path.push((t, false)); t = r; },
+                Node(ref l, _, ref r) => return rebuild(path, Tree::glue(l, r))
+            }
+        }
+    }
+
+    // The set operations split the second tree around the root of the first one and recurse on
+    // both halves, so they take O(m log(n/m + 1)) time on balanced trees of sizes m <= n and
+    // share every subtree that one of the arguments contributes whole. As the tree is not
+    // rebalanced, joining two halves around an element is just building a node.
+    fn union(&self, t: &Tree<T>) -> Tree<T> {
+        match (self, t) {
+            (_, &Tip) => self.clone(),
+            (&Tip, _) => t.clone(),
+            (&Node(ref l1, ref x, ref r1), _) => {
+                let (l2, _, r2) = t.split(x);
+                Node(Ptr::new(l1.union(&l2)), x.clone(), Ptr::new(r1.union(&r2)))
+            }
+        }
+    }
+
+    fn intersection(&self, t: &Tree<T>) -> Tree<T> {
+        match (self, t) {
+            (_, &Tip) | (&Tip, _) => Tip,
+            (&Node(ref l1, ref x, ref r1), _) => {
+                let (l2, found, r2) = t.split(x);
+                let l = Ptr::new(l1.intersection(&l2));
+                let r = Ptr::new(r1.intersection(&r2));
+
+                if found { Node(l, x.clone(), r) } else { Tree::glue(&l, &r) }
+            }
+        }
+    }
+
+    fn difference(&self, t: &Tree<T>) -> Tree<T> {
+        match (self, t) {
+            (_, &Tip) => self.clone(),
+            (&Tip, _) => Tip,
+            (_, &Node(ref l2, ref x, ref r2)) => {
+                let (l1, _, r1) = self.split(x);
+                Tree::glue(&Ptr::new(l1.difference(l2)), &Ptr::new(r1.difference(r2)))
+            }
+        }
+    }
+
+    fn is_subset(&self, t: &Tree<T>) -> bool {
+        match (self, t) {
+            (&Tip, _) => true,
+            (_, &Tip) => false,
+            (&Node(ref l1, ref x, ref r1), _) => {
+                let (l2, found, r2) = t.split(x);
+                found && l1.is_subset(&l2) && r1.is_subset(&r2)
+            }
+        }
+    }
+}
+
+fn identity<T>(x: &T) -> &T {
+    x
+}
+
+impl<T: Ord + Clone> OrderedSet<T> for Tree<T> {
+    fn min(&self) -> Option<T> {
+        self.min_elem().cloned()
+    }
+
+    fn max(&self) -> Option<T> {
+        self.max_elem().cloned()
+    }
+
+    fn floor(&self, x: &T) -> Option<T> {
+        self.floor_by(x, identity).cloned()
+    }
+
+    fn ceiling(&self, x: &T) -> Option<T> {
+        self.ceiling_by(x, identity).cloned()
+    }
+
+    fn range<'a>(&'a self, r: ops::Range<T>) -> Box<dyn Iterator<Item=&'a T> + 'a> where T: 'a {
+        Box::new(self.range_by(r.start, r.end, identity))
+    }
+}
+
+// collecting binds every element by its key, like `Map::bind`, so that a pair replaces an earlier
+// one with the same key instead of becoming a second binding of it
+impl<T: Keyed + Clone> FromIterator<T> for Tree<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Tree<T> {
+        let mut t = Tip;
+        t.extend(iter);
+        t
+    }
+}
+
+impl<T: Keyed + Clone> Extend<T> for Tree<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for x in iter {
+            *self = bind_keyed(self, x);
+        }
+    }
+}
+
+// inserts `x`, replacing an element with the same key unless it is equal to `x`
+fn bind_keyed<T: Keyed + Clone>(t: &Tree<T>, x: T) -> Tree<T> {
+    let mut path = vec![];
+    let mut s = t;
+
+    loop {
+        match *s {
+            Tip => return rebuild(path, Node(Ptr::new(Tip), x, Ptr::new(Tip))),
+            Node(ref l, ref v, ref r) =>
+                match x.cmp_key(v) {
+                    Ordering::Less => { path.push((s, true)); s = l; },
+                    Ordering::Greater => { path.push((s, false)); s = r; },
+                    Ordering::Equal if x == *v => return t.clone(),
+                    Ordering::Equal => return rebuild(path, Node(l.clone(), x, r.clone()))
+                }
         }
     }
 }
@@ -41,27 +290,27 @@ fn treeset() {
 
     assert_eq!(t2,
             Node(
-                Rc::new(Node(
-                    Rc::new(Node(
-                        Rc::new(Tip),
+                Ptr::new(Node(
+                    Ptr::new(Node(
+                        Ptr::new(Tip),
                         1,
-                        Rc::new(Tip))),
+                        Ptr::new(Tip))),
                     4,
-                    Rc::new(Node(
-                        Rc::new(Tip),
+                    Ptr::new(Node(
+                        Ptr::new(Tip),
                         5,
-                        Rc::new(Tip))))),
+                        Ptr::new(Tip))))),
                 6,
-                Rc::new(Node(
-                    Rc::new(Node(
-                        Rc::new(Tip),
+                Ptr::new(Node(
+                    Ptr::new(Node(
+                        Ptr::new(Tip),
                         7,
-                        Rc::new(Tip))),
+                        Ptr::new(Tip))),
                     8,
-                    Rc::new(Node(
-                        Rc::new(Tip),
+                    Ptr::new(Node(
+                        Ptr::new(Tip),
                         9,
-                        Rc::new(Tip)))))));
+                        Ptr::new(Tip)))))));
 
     assert!(t2.member(1));
     assert!(t2.member(9));
@@ -72,4 +321,177 @@ fn treeset() {
 
     assert_eq!(t2.member(1), t2.member2(1));
     assert_eq!(t2.member(0), t2.member2(0));
+
+    assert_eq!(t2.iter().cloned().collect::<Vec<usize>>(), vec![1, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(vec![6, 8, 9, 7, 4, 5, 1].into_iter().collect::<Tree<usize>>(), t2);
+
+    let t3 = t2.delete(6).delete(1).delete(8);
+
+    assert!(!t3.member(6));
+    assert!(!t3.member(1));
+    assert!(!t3.member(8));
+    assert!(t3.member(4));
+    assert!(t3.member(5));
+    assert!(t3.member(7));
+    assert!(t3.member(9));
+    assert!(t2.member(6));
+
+    assert_eq!(t3, t.insert(7).insert(4).insert(9).insert(5));
+
+    // inserting an existing element shares both children of the root instead of copying them
+    let t4 = t2.insert(5);
+    assert_eq!(t4, t2);
+
+    match (&t2, &t4) {
+        (&Node(ref l1, _, ref r1), &Node(ref l2, _, ref r2)) => {
+            assert!(Ptr::ptr_eq(l1, l2));
+            assert!(Ptr::ptr_eq(r1, r2));
+        },
+        _ => panic!("missing node")
+    }
+
+    assert_eq!(t2.delete(3), t2);
+    assert_eq!(t.delete(3), t);
+}
+
+#[test]
+fn treeset_algebra() {
+    use std::collections::BTreeSet;
+
+    use model::Rng;
+
+    let mut rng = Rng(88172645);
+
+    for round in 0..200 {
+        // small ranges give plenty of overlap, the sizes go from empty to a few hundred
+        let (n, m, range) = (rng.next() % (round + 1) * 2, rng.next() % 300, rng.next() % 500 + 1);
+        let xs: Vec<u32> = (0..n).map(|_| rng.next() % range).collect();
+        let ys: Vec<u32> = (0..m).map(|_| rng.next() % range).collect();
+
+        let t1: Tree<u32> = xs.iter().cloned().collect();
+        let t2: Tree<u32> = ys.iter().cloned().collect();
+        let s1: BTreeSet<u32> = xs.iter().cloned().collect();
+        let s2: BTreeSet<u32> = ys.iter().cloned().collect();
+
+        assert_eq!(t1.union(&t2).iter().cloned().collect::<Vec<u32>>(),
+                   s1.union(&s2).cloned().collect::<Vec<u32>>());
+        assert_eq!(t1.intersection(&t2).iter().cloned().collect::<Vec<u32>>(),
+                   s1.intersection(&s2).cloned().collect::<Vec<u32>>());
+        assert_eq!(t1.difference(&t2).iter().cloned().collect::<Vec<u32>>(),
+                   s1.difference(&s2).cloned().collect::<Vec<u32>>());
+        assert_eq!(t1.is_subset(&t2), s1.is_subset(&s2));
+
+        assert!(t1.intersection(&t2).is_subset(&t1));
+        assert!(t1.is_subset(&t1.union(&t2)));
+        assert!(t1.difference(&t2).intersection(&t2).iter().next().is_none());
+    }
+
+    let t: Tree<usize> = (0..10).collect();
+    let (l, found, r) = t.split(&4);
+    assert_eq!(l.iter().cloned().collect::<Vec<usize>>(), vec![0, 1, 2, 3]);
+    assert!(found);
+    assert_eq!(r.iter().cloned().collect::<Vec<usize>>(), vec![5, 6, 7, 8, 9]);
+    assert!(!t.split(&10).1);
+
+    // the empty set is an identity for union and difference
+    let e: Tree<usize> = Set::empty();
+    assert_eq!(t.union(&e), t);
+    assert_eq!(t.difference(&e), t);
+    assert!(e.is_subset(&t));
+    assert!(!t.is_subset(&e));
+}
+
+#[test]
+fn treeset_order() {
+    use std::collections::BTreeSet;
+
+    use model::Rng;
+
+    let t: Tree<usize> = vec![6, 8, 9, 7, 4, 5, 1].into_iter().collect();
+    let e: Tree<usize> = Set::empty();
+
+    assert_eq!(t.min(), Some(1));
+    assert_eq!(t.max(), Some(9));
+    assert_eq!(e.min(), None);
+    assert_eq!(e.max(), None);
+
+    assert_eq!(t.floor(&3), Some(1));
+    assert_eq!(t.floor(&4), Some(4));
+    assert_eq!(t.floor(&0), None);
+    assert_eq!(t.ceiling(&2), Some(4));
+    assert_eq!(t.ceiling(&9), Some(9));
+    assert_eq!(t.ceiling(&10), None);
+
+    assert_eq!(t.range(4..8).cloned().collect::<Vec<usize>>(), vec![4, 5, 6, 7]);
+    assert_eq!(t.range(2..3).count(), 0);
+    assert_eq!(t.range(0..100).count(), 7);
+
+    let mut rng = Rng(1597334677);
+
+    let xs: Vec<u32> = (0..500).map(|_| rng.next() % 1000).collect();
+    let t: Tree<u32> = xs.iter().cloned().collect();
+    let model: BTreeSet<u32> = xs.iter().cloned().collect();
+
+    for _ in 0..500 {
+        let (x, y) = (rng.next() % 1100, rng.next() % 1100);
+
+        assert_eq!(t.floor(&x), model.range(..x + 1).next_back().cloned());
+        assert_eq!(t.ceiling(&x), model.range(x..).next().cloned());
+
+        if x <= y {
+            assert_eq!(t.range(x..y).cloned().collect::<Vec<u32>>(),
+                       model.range(x..y).cloned().collect::<Vec<u32>>());
+        }
+    }
+
+    assert_eq!(t.min(), model.iter().next().cloned());
+    assert_eq!(t.max(), model.iter().next_back().cloned());
+}
+
+#[test]
+fn insert_comparisons() {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+
+    thread_local!(static COMPARISONS: Cell<usize> = Cell::new(0));
+
+    #[derive(Clone, Debug, Eq)]
+    struct Counted(usize);
+
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Counted) -> bool {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0 == other.0
+        }
+    }
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Counted) -> Ordering {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    // a complete tree of depth 4 holding 1..15
+    let t: Tree<Counted> = Tree::from_sorted_iter((1..16).map(Counted));
+
+    for x in 0..17 {
+        COMPARISONS.with(|c| c.set(0));
+        let t2 = t.insert(Counted(x));
+        assert!(COMPARISONS.with(|c| c.get()) <= 4 + 1);
+
+        if x >= 1 && x <= 15 {
+            match (&t, &t2) {
+                (&Node(ref l1, _, ref r1), &Node(ref l2, _, ref r2)) =>
+                    assert!(Ptr::ptr_eq(l1, l2) && Ptr::ptr_eq(r1, r2)),
+                _ => panic!("missing node")
+            }
+        }
+    }
 }
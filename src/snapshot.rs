@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use ptr::Ptr;
+use stack::List;
+use stack::List::{Cons, Nil};
+use tree::Tree;
+use tree::Tree::{Node, Tip};
+
+// A binary format for several versions of a `Tree` or a `List` at once, which keeps the sharing
+// between them: every node that is reachable from more than one version, or more than once from
+// the same one, is written once and is shared again when it is read back.
+//
+// A snapshot starts with a magic number telling trees and lists apart, followed by the number of
+// nodes and the nodes themselves, children before their parents. A tree node is the reference to
+// its left child, its element and the reference to its right child, a list node is its element
+// followed by the reference to its tail. A reference is 0 for an empty tree or list and i + 1 for
+// the i-th node. The snapshot ends with the number of versions and a reference to each of their
+// roots. Counts and references are unsigned LEB128 integers.
+
+const TREE_MAGIC: &'static [u8] = b"OKT1";
+const LIST_MAGIC: &'static [u8] = b"OKL1";
+
+// the elements are written with their own compact encoding, integers in little-endian order
+pub trait Element: Sized {
+    fn encode<W: Write>(&self, &mut W) -> io::Result<()>;
+    fn decode<R: Read>(&mut R) -> io::Result<Self>;
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_varint<W: Write>(w: &mut W, n: u64) -> io::Result<()> {
+    let mut n = n;
+
+    loop {
+        let b = (n & 0x7f) as u8;
+        n = n >> 7;
+
+        if n == 0 {
+            return w.write_all(&[b]);
+        }
+
+        try!(w.write_all(&[b | 0x80]));
+    }
+}
+
+fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut n = 0;
+    let mut shift = 0;
+
+    loop {
+        let mut b = [0];
+        try!(r.read_exact(&mut b));
+
+        if shift > 63 || (shift == 63 && b[0] > 1) {
+            return Err(invalid("varint overflows 64 bits"));
+        }
+
+        n = n | ((b[0] & 0x7f) as u64) << shift;
+        shift = shift + 7;
+
+        if b[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+}
+
+// reads a reference to one of the `n` nodes read so far, so that a snapshot can't contain cycles
+fn read_ref<R: Read>(r: &mut R, n: usize) -> io::Result<usize> {
+    let i = try!(read_varint(r));
+
+    if i > n as u64 {
+        return Err(invalid("reference to a node that wasn't read yet"));
+    }
+
+    Ok(i as usize)
+}
+
+fn read_count<R: Read>(r: &mut R) -> io::Result<usize> {
+    let n = try!(read_varint(r));
+
+    if n > usize::max_value() as u64 {
+        return Err(invalid("count too large"));
+    }
+
+    Ok(n as usize)
+}
+
+fn read_magic<R: Read>(r: &mut R, magic: &[u8]) -> io::Result<()> {
+    let mut m = [0; 4];
+    try!(r.read_exact(&mut m));
+
+    if m != magic {
+        return Err(invalid("not a snapshot of the expected kind"));
+    }
+
+    Ok(())
+}
+
+pub fn write_trees<T: Element, W: Write>(w: &mut W, versions: &[Tree<T>]) -> io::Result<()> {
+    fn id<T>(ids: &HashMap<*const Tree<T>, u64>, t: &Tree<T>) -> u64 {
+        match *t {
+            Tip => 0,
+            Node(..) => ids[&(t as *const Tree<T>)]
+        }
+    }
+
+    // numbers the nodes in post-order, walking from an explicit stack as trees can be degenerate
+    let mut ids = HashMap::new();
+    let mut nodes = vec![];
+
+    for v in versions {
+        let mut stack = vec![(v, false)];
+
+        while let Some((t, expanded)) = stack.pop() {
+            if let Node(ref l, _, ref r) = *t {
+                if ids.contains_key(&(t as *const Tree<T>)) {
+                    continue;
+                }
+
+                if expanded {
+                    nodes.push(t);
+                    ids.insert(t as *const Tree<T>, nodes.len() as u64);
+                } else {
+                    stack.push((t, true));
+                    stack.push((r, false));
+                    stack.push((l, false));
+                }
+            }
+        }
+    }
+
+    try!(w.write_all(TREE_MAGIC));
+    try!(write_varint(w, nodes.len() as u64));
+
+    for t in nodes {
+        if let Node(ref l, ref x, ref r) = *t {
+            try!(write_varint(w, id(&ids, l)));
+            try!(x.encode(w));
+            try!(write_varint(w, id(&ids, r)));
+        }
+    }
+
+    try!(write_varint(w, versions.len() as u64));
+
+    for v in versions {
+        try!(write_varint(w, id(&ids, v)));
+    }
+
+    Ok(())
+}
+
+pub fn read_trees<T: Element + Clone, R: Read>(r: &mut R) -> io::Result<Vec<Tree<T>>> {
+    try!(read_magic(r, TREE_MAGIC));
+
+    let tip = Ptr::new(Tip);
+    let n = try!(read_count(r));
+    let mut nodes: Vec<Ptr<Tree<T>>> = vec![];
+
+    for _ in 0..n {
+        let l = try!(read_ref(r, nodes.len()));
+        let x = try!(T::decode(r));
+        let rt = try!(read_ref(r, nodes.len()));
+
+        let child = |i: usize| if i == 0 { tip.clone() } else { nodes[i - 1].clone() };
+        let t = Node(child(l), x, child(rt));
+        nodes.push(Ptr::new(t));
+    }
+
+    let m = try!(read_count(r));
+    let mut versions = vec![];
+
+    for _ in 0..m {
+        match try!(read_ref(r, nodes.len())) {
+            0 => versions.push(Tip),
+            i => versions.push((*nodes[i - 1]).clone())
+        }
+    }
+
+    Ok(versions)
+}
+
+pub fn write_lists<T: Element, W: Write>(w: &mut W, versions: &[List<T>]) -> io::Result<()> {
+    fn id<T>(ids: &HashMap<*const List<T>, u64>, l: &List<T>) -> u64 {
+        match *l {
+            Nil => 0,
+            Cons(..) => ids[&(l as *const List<T>)]
+        }
+    }
+
+    // numbers the nodes of every version from the last one of the part that isn't numbered yet
+    let mut ids = HashMap::new();
+    let mut nodes = vec![];
+
+    for v in versions {
+        let mut path = vec![];
+        let mut l = v;
+
+        while let Cons(_, ref t) = *l {
+            if ids.contains_key(&(l as *const List<T>)) {
+                break;
+            }
+
+            path.push(l);
+            l = t;
+        }
+
+        for l in path.into_iter().rev() {
+            nodes.push(l);
+            ids.insert(l as *const List<T>, nodes.len() as u64);
+        }
+    }
+
+    try!(w.write_all(LIST_MAGIC));
+    try!(write_varint(w, nodes.len() as u64));
+
+    for l in nodes {
+        if let Cons(ref x, ref t) = *l {
+            try!(x.encode(w));
+            try!(write_varint(w, id(&ids, t)));
+        }
+    }
+
+    try!(write_varint(w, versions.len() as u64));
+
+    for v in versions {
+        try!(write_varint(w, id(&ids, v)));
+    }
+
+    Ok(())
+}
+
+pub fn read_lists<T: Element + Clone, R: Read>(r: &mut R) -> io::Result<Vec<List<T>>> {
+    try!(read_magic(r, LIST_MAGIC));
+
+    let nil = Ptr::new(Nil);
+    let n = try!(read_count(r));
+    let mut nodes: Vec<Ptr<List<T>>> = vec![];
+
+    for _ in 0..n {
+        let x = try!(T::decode(r));
+        let t = match try!(read_ref(r, nodes.len())) {
+            0 => nil.clone(),
+            i => nodes[i - 1].clone()
+        };
+
+        nodes.push(Ptr::new(Cons(x, t)));
+    }
+
+    let m = try!(read_count(r));
+    let mut versions = vec![];
+
+    for _ in 0..m {
+        match try!(read_ref(r, nodes.len())) {
+            0 => versions.push(Nil),
+            i => versions.push((*nodes[i - 1]).clone())
+        }
+    }
+
+    Ok(versions)
+}
+
+macro_rules! element_int {
+    ($($t: ty),*) => {$(
+        impl Element for $t {
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+
+            fn decode<R: Read>(r: &mut R) -> io::Result<$t> {
+                let mut b = [0; ::std::mem::size_of::<$t>()];
+                try!(r.read_exact(&mut b));
+                Ok(<$t>::from_le_bytes(b))
+            }
+        }
+    )*}
+}
+
+element_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+// as 64 bit integers, so that snapshots can be read on platforms of any width
+impl Element for usize {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u64).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<usize> {
+        let n = try!(u64::decode(r));
+
+        if n > usize::max_value() as u64 {
+            return Err(invalid("usize out of range"));
+        }
+
+        Ok(n as usize)
+    }
+}
+
+impl Element for bool {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u8).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<bool> {
+        match try!(u8::decode(r)) {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("invalid bool"))
+        }
+    }
+}
+
+impl Element for char {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u32).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<char> {
+        let c = try!(u32::decode(r));
+        ::std::char::from_u32(c).ok_or_else(|| invalid("invalid char"))
+    }
+}
+
+// the length followed by the UTF-8 bytes
+impl Element for String {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        try!(write_varint(w, self.len() as u64));
+        w.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<String> {
+        let n = try!(read_count(r));
+        let mut b = vec![];
+        try!(r.take(n as u64).read_to_end(&mut b));
+
+        if b.len() != n {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated string"));
+        }
+
+        String::from_utf8(b).map_err(|_| invalid("invalid UTF-8"))
+    }
+}
+
+// the bindings of the map view of a tree
+impl<A: Element, B: Element> Element for (A, B) {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        try!(self.0.encode(w));
+        self.1.encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> io::Result<(A, B)> {
+        let a = try!(A::decode(r));
+        let b = try!(B::decode(r));
+        Ok((a, b))
+    }
+}
+
+#[test]
+fn tree_snapshot() {
+    use model::Rng;
+    use set::Set;
+
+    let mut rng = Rng(123456789);
+
+    // ten versions of a tree of a thousand elements, each one inserting ten more into the
+    // previous one
+    let mut versions: Vec<Tree<u32>> = vec![];
+    let mut t: Tree<u32> = (0..1000).map(|_| rng.next()).collect();
+
+    for _ in 0..10 {
+        for _ in 0..10 {
+            t = t.insert(rng.next());
+        }
+
+        versions.push(t.clone());
+    }
+
+    let mut buf = vec![];
+    write_trees(&mut buf, &versions).unwrap();
+
+    // written separately, every version would repeat the nodes it shares with the previous ones
+    let separate: usize = versions.iter().map(|v| {
+        let mut b = vec![];
+        write_trees(&mut b, &[v.clone()]).unwrap();
+        b.len()
+    }).sum();
+    assert!(buf.len() * 2 < separate);
+
+    let read: Vec<Tree<u32>> = read_trees(&mut &buf[..]).unwrap();
+    assert_eq!(read, versions);
+
+    // the versions read back share as many nodes as the original ones
+    fn distinct_nodes<T>(versions: &[Tree<T>]) -> usize {
+        use std::collections::HashSet;
+
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&Tree<T>> = versions.iter().collect();
+
+        while let Some(t) = stack.pop() {
+            if let Node(ref l, _, ref r) = *t {
+                for c in vec![l, r] {
+                    if let Node(..) = **c {
+                        if seen.insert(&**c as *const Tree<T>) {
+                            stack.push(c);
+                        }
+                    }
+                }
+            }
+        }
+
+        seen.len()
+    }
+
+    assert_eq!(distinct_nodes(&read), distinct_nodes(&versions));
+    assert!(distinct_nodes(&read) < 2 * 1100);
+
+    // maps and empty trees
+    let m: Tree<(String, bool)> = vec![("a".to_string(), true), ("é".to_string(), false)].into_iter().collect();
+    let mut buf = vec![];
+    write_trees(&mut buf, &[m.clone(), Tip]).unwrap();
+    assert_eq!(read_trees(&mut &buf[..]).unwrap(), vec![m, Tip]);
+
+    // corrupted input
+    assert!(read_trees::<u32, _>(&mut &b"OKL1"[..]).is_err());
+    assert!(read_trees::<u32, _>(&mut &b"OKT1\x01\x01"[..]).is_err());
+    assert!(read_trees::<u32, _>(&mut &buf[..buf.len() - 1]).is_err());
+}
+
+#[test]
+fn list_snapshot() {
+    use stack::Stack;
+
+    let n = 1000000;
+
+    // a long list and two branches off it, which share all of it
+    let mut l: List<u64> = Nil;
+    for i in 0..n {
+        l = l.cons(i);
+    }
+
+    let versions = vec![l.cons(1).cons(2), l.cons(3), l.clone(), Nil];
+
+    let mut buf = vec![];
+    write_lists(&mut buf, &versions).unwrap();
+
+    let read: Vec<List<u64>> = read_lists(&mut &buf[..]).unwrap();
+    assert_eq!(read, versions);
+
+    // both branches end up in the tail of `l`
+    fn tail<T>(l: &List<T>) -> &Ptr<List<T>> {
+        match *l {
+            Cons(_, ref t) => t,
+            Nil => panic!("missing node")
+        }
+    }
+
+    assert!(Ptr::ptr_eq(tail(tail(tail(&read[0]))), tail(tail(&read[1]))));
+    assert!(Ptr::ptr_eq(tail(tail(&read[1])), tail(&read[2])));
+
+    assert!(read_lists::<u64, _>(&mut &b"OKT1"[..]).is_err());
+}
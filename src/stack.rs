@@ -1,20 +1,46 @@
 use std::fmt::{Display, Error, Formatter};
-use std::rc::Rc;
+use std::iter::FromIterator;
+use std::mem;
+
+use dot::Dot;
+use error::EmptyError;
+use ptr::Ptr;
+use tree_layout::LayoutTree;
 
 pub trait Stack<T> {
     fn empty() -> Self;
     fn is_empty(&self) -> bool;
 
     fn cons(&self, T) -> Self;
-    fn head(&self) -> T;
-    fn tail(&self) -> Self;
+    fn try_head(&self) -> Result<T, EmptyError>;
+    fn try_tail(&self) -> Result<Self, EmptyError> where Self: Sized;
+
+    fn head(&self) -> T {
+        match self.try_head() {
+            Ok(x) => x,
+            Err(_) => panic!("head of empty stack")
+        }
+    }
 
+    fn tail(&self) -> Self where Self: Sized {
+        match self.try_tail() {
+            Ok(t) => t,
+            Err(_) => panic!("tail of empty stack")
+        }
+    }
+
+    // both `append` and `update` copy the prefix they walk over into a vector and cons it back
+    // in reverse, instead of recursing once per element
     fn append(&self, y: &Self) -> Self where Self: Clone + Sized {
-        if self.is_empty() {
-            y.clone()
-        } else {
-            self.tail().append(y).cons(self.head())
+        let mut xs = vec![];
+        let mut l = self.clone();
+
+        while !l.is_empty() {
+            xs.push(l.head());
+            l = l.tail();
         }
+
+        xs.into_iter().rev().fold(y.clone(), |acc, x| acc.cons(x))
     }
 
     fn update(&self, i: usize, x: T) -> Self where Self: Sized {
@@ -22,22 +48,82 @@ pub trait Stack<T> {
             panic!("index out of bounds");
         }
 
-        if i == 0 {
-            self.tail().cons(x)
-        } else {
-            self.tail().update(i - 1, x).cons(self.head())
+        let mut xs = vec![];
+        let mut l = self.tail();
+
+        if i > 0 {
+            xs.push(self.head());
         }
+
+        for _ in 1..i {
+            if l.is_empty() {
+                panic!("index out of bounds");
+            }
+
+            xs.push(l.head());
+            l = l.tail();
+        }
+
+        let l = if i == 0 {
+            l.cons(x)
+        } else if l.is_empty() {
+            panic!("index out of bounds")
+        } else {
+            l.tail().cons(x)
+        };
+
+        xs.into_iter().rev().fold(l, |acc, h| acc.cons(h))
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum List<T> {
     Nil,
-    Cons(T, Rc<List<T>>)
+    Cons(T, Ptr<List<T>>)
 }
 
 use stack::List::{Cons, Nil};
 
+// The derived implementations would recurse once per element, which overflows the stack on long
+// lists. When the tail is only owned by the node being dropped, it is moved out and dropped in the
+// loop instead, leaving a `Nil` behind.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        fn take_tail<T>(l: &mut List<T>) -> Option<List<T>> {
+            match *l {
+                Cons(_, ref mut t) => Ptr::get_mut(t).map(|t| mem::replace(t, Nil)),
+                Nil => None
+            }
+        }
+
+        let mut next = take_tail(self);
+
+        while let Some(mut l) = next {
+            next = take_tail(&mut l);
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &List<T>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Clone> List<T> {
+    pub fn reverse(&self) -> List<T> {
+        let mut acc = Nil;
+
+        for h in self.iter() {
+            acc = Cons(h.clone(), Ptr::new(acc));
+        }
+
+        acc
+    }
+}
+
 impl<T: Clone> Stack<T> for List<T> {
     fn empty() -> List<T> {
         Nil
@@ -52,44 +138,105 @@ impl<T: Clone> Stack<T> for List<T> {
 
     fn cons(&self, x: T) -> List<T> {
         match *self {
-            Cons(ref h, ref t) => Cons(x, Rc::new(Cons(h.clone(), t.clone()))),
-            Nil => Cons(x, Rc::new(Nil))
+            Cons(ref h, ref t) => Cons(x, Ptr::new(Cons(h.clone(), t.clone()))),
+            Nil => Cons(x, Ptr::new(Nil))
         }
     }
 
-    fn head(&self) -> T {
+    fn try_head(&self) -> Result<T, EmptyError> {
+        match *self {
+            Cons(ref h, _) => Ok(h.clone()),
+            Nil => Err(EmptyError)
+        }
+    }
+
+    fn try_tail(&self) -> Result<List<T>, EmptyError> {
         match *self {
-            Cons(ref h, _) => h.clone(),
-            Nil => panic!("head of empty list")
+            Nil => Err(EmptyError),
+            Cons(_, ref t) => Ok((**t).clone())
         }
     }
+}
 
-    fn tail(&self) -> List<T> {
+// a list is drawn as a tree in which every node has its tail as only child
+impl<T: Clone> LayoutTree for List<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&List<T>>)> {
         match *self {
-            Nil => panic!("tail of empty list"),
-            Cons(_, ref t) => (**t).clone()
+            Cons(ref x, ref t) => Some((x.clone(), vec![&**t])),
+            Nil => None
+        }
+    }
+}
+
+impl<T: Clone> Dot for List<T> {
+    type Node = List<T>;
+
+    fn dot_roots(&self) -> Vec<&List<T>> {
+        vec![self]
+    }
+}
+
+// iterates over the elements from head to tail
+pub struct Iter<'a, T: 'a> {
+    next: &'a List<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match *self.next {
+            Cons(ref h, ref t) => {
+                self.next = t;
+                Some(h)
+            },
+            Nil => None
         }
     }
 }
 
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<T> {
+        Iter { next: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// the first element of the iterator becomes the head of the list
+impl<T: Clone> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> List<T> {
+        let xs: Vec<T> = iter.into_iter().collect();
+        xs.into_iter().rev().fold(Nil, |l, x| Cons(x, Ptr::new(l)))
+    }
+}
+
+// appends the elements at the end of the list, copying it
+impl<T: Clone> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        let l: List<T> = iter.into_iter().collect();
+        *self = self.append(&l);
+    }
+}
+
 impl<T: Display> Display for List<T> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         try!(write!(f, "["));
 
-        fn _loop<T: Display>(f: &mut Formatter, l: &List<T>, first: bool) -> Result<(), Error> {
-            match *l {
-                Cons(ref h, ref t) => {
-                    if first { try!(write!(f, "{}", h)); }
-                    else { try!(write!(f, ", {}", h)); }
-
-                    _loop(f, t, false)
-                },
-                Nil => Result::Ok(())
-            }
+        for (i, h) in self.iter().enumerate() {
+            if i == 0 { try!(write!(f, "{}", h)); }
+            else { try!(write!(f, ", {}", h)); }
         }
 
-        try!(_loop(f, self, true));
-
         write!(f, "]")
     }
 }
@@ -102,22 +249,56 @@ fn list() {
 
     assert!(l1.is_empty());
     assert!(!l2.is_empty());
-    assert_eq!(l2, Cons(1, Rc::new(Cons(2, Rc::new(Cons(3, Rc::new(Nil)))))));
+    assert_eq!(l2, Cons(1, Ptr::new(Cons(2, Ptr::new(Cons(3, Ptr::new(Nil)))))));
     assert_eq!(l2.head(), 1);
-    assert_eq!(l2.tail(), Cons(2, Rc::new(Cons(3, Rc::new(Nil)))));
+    assert_eq!(l2.tail(), Cons(2, Ptr::new(Cons(3, Ptr::new(Nil)))));
+
+    assert_eq!(l1.try_head(), Err(EmptyError));
+    assert_eq!(l1.try_tail(), Err(EmptyError));
+    assert_eq!(l2.try_head(), Ok(1));
+    assert_eq!(l2.try_tail(), Ok(l2.tail()));
 
     assert_eq!(l1.append(&l2), l2);
     assert_eq!(l2.append(&l1), l2);
 
     assert_eq!(l2.append(&l3),
-               Cons(1, Rc::new(Cons(2, Rc::new(Cons(3, Rc::new(Cons(4, Rc::new(Cons(5, Rc::new(Nil)))))))))));
+               Cons(1, Ptr::new(Cons(2, Ptr::new(Cons(3, Ptr::new(Cons(4, Ptr::new(Cons(5, Ptr::new(Nil)))))))))));
 
     assert_eq!(l3.append(&l2),
-               Cons(4, Rc::new(Cons(5, Rc::new(Cons(1, Rc::new(Cons(2, Rc::new(Cons(3, Rc::new(Nil)))))))))));
+               Cons(4, Ptr::new(Cons(5, Ptr::new(Cons(1, Ptr::new(Cons(2, Ptr::new(Cons(3, Ptr::new(Nil)))))))))));
 
     assert_eq!(l3.update(0, 0),
-               Cons(0, Rc::new(Cons(5, Rc::new(Nil)))));
+               Cons(0, Ptr::new(Cons(5, Ptr::new(Nil)))));
 
     assert_eq!(l3.update(1, 0),
-               Cons(4, Rc::new(Cons(0, Rc::new(Nil)))));
+               Cons(4, Ptr::new(Cons(0, Ptr::new(Nil)))));
+
+    assert_eq!(l2.iter().cloned().collect::<Vec<usize>>(), vec![1, 2, 3]);
+    assert_eq!(l2.reverse(), l1.cons(1).cons(2).cons(3));
+    assert_eq!(vec![1, 2, 3].into_iter().collect::<List<usize>>(), l2);
+
+    let mut l4 = l2.clone();
+    l4.extend(vec![4, 5]);
+    assert_eq!(l4, l2.append(&l3));
+}
+
+#[test]
+fn long_list() {
+    let n = 1000000;
+
+    let mut l: List<usize> = Stack::empty();
+    for i in (0..n).rev() {
+        l = l.cons(i);
+    }
+
+    let l2 = l.append(&l);
+    assert_eq!(l2.iter().count(), 2 * n);
+
+    let l3 = l.update(n - 1, 0);
+    assert_eq!(l3.iter().last(), Some(&0));
+    assert!(l3 != l);
+    assert_eq!(l3.update(n - 1, n - 1), l);
+
+    let digits: usize = (0..n).map(|i| i.to_string().len()).sum();
+    assert_eq!(format!("{}", l).len(), digits + 2 * (n - 1) + 2);
 }
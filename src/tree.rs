@@ -1,49 +1,265 @@
 use std::cmp::max;
+use std::cmp::Ordering::{Equal, Greater, Less};
 use std::fmt::{Display, Error, Formatter};
-use std::num::Int;
-use std::rc::Rc;
+use std::mem;
+
+use dot::Dot;
+use ptr::Ptr;
+use tree_layout::LayoutTree;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Tree<T> {
     Tip,
-    Node(Rc<Tree<T>>, T, Rc<Tree<T>>),
+    Node(Ptr<Tree<T>>, T, Ptr<Tree<T>>),
 }
 
 use tree::Tree::{Node, Tip};
 
+// The derived implementation would recurse once per level, which overflows the stack on
+// degenerate trees, so uniquely owned subtrees are moved out and dropped from an explicit stack.
+impl<T> Drop for Tree<T> {
+    fn drop(&mut self) {
+        fn take_children<T>(t: &mut Tree<T>, stack: &mut Vec<Tree<T>>) {
+            if let Node(ref mut l, _, ref mut r) = *t {
+                if let Some(l) = Ptr::get_mut(l) { stack.push(mem::replace(l, Tip)); }
+                if let Some(r) = Ptr::get_mut(r) { stack.push(mem::replace(r, Tip)); }
+            }
+        }
+
+        let mut stack = vec![];
+        take_children(self, &mut stack);
+
+        while let Some(mut t) = stack.pop() {
+            take_children(&mut t, &mut stack);
+        }
+    }
+}
+
 impl<T: Display> Display for Tree<T> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        fn aux<T: Display>(f: &mut Formatter, t: &Tree<T>, right: bool, indent: &str) -> Result<(), Error> {
-            match *t {
-                Node(ref l, ref x, ref r) => {
-                    try!(aux(f, r, true, &(indent.to_string() + if right { "        " } else { " |      " })));
+        // the right subtree is printed above a node and the left one below it, `Visit` frames are
+        // expanded into the lines of a subtree and `Line` frames are written out as they are
+        enum Frame<'a, T: 'a> {
+            Visit(&'a Tree<T>, bool, String),
+            Line(String, &'a T),
+        }
 
-                    try!(write!(f, "{}", indent));
-                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
-                    try!(write!(f, "{}", "----- "));
+        fn arrow(right: bool) -> &'static str {
+            if right { " /----- " } else { " \\----- " }
+        }
 
-                    try!(writeln!(f, "({})", x));
+        let mut stack = match *self {
+            Node(ref l, ref x, ref r) =>
+                vec![Frame::Visit(l, false, "".to_string()),
+                     Frame::Line("".to_string(), x),
+                     Frame::Visit(r, true, "".to_string())],
+            Tip => vec![]
+        };
 
-                    aux(f, l, false, &(indent.to_string() + if right { " |      " } else { "        " }))
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Visit(&Node(ref l, ref x, ref r), right, indent) => {
+                    stack.push(Frame::Visit(l, false, indent.clone() + if right { " |      " } else { "        " }));
+                    stack.push(Frame::Line(indent.clone() + arrow(right), x));
+                    stack.push(Frame::Visit(r, true, indent + if right { "        " } else { " |      " }));
                 },
-                Tip => {
-                    try!(write!(f, "{}", indent));
-                    try!(if right { write!(f, "{}", " /") } else { write!(f, "{}", " \\") });
-                    try!(write!(f, "{}", "----- "));
-
-                    writeln!(f, "{}", "()")
-                }
+                Frame::Visit(&Tip, right, indent) =>
+                    try!(writeln!(f, "{}{}()", indent, arrow(right))),
+                Frame::Line(prefix, x) =>
+                    try!(writeln!(f, "{}({})", prefix, x))
             }
         }
 
+        Result::Ok(())
+    }
+}
+
+impl<T: Clone> LayoutTree for Tree<T> {
+    type Label = T;
+
+    fn node(&self) -> Option<(T, Vec<&Tree<T>>)> {
         match *self {
-            Node(ref l, ref x, ref r) => {
-                try!(aux(f, r, true, ""));
-                try!(writeln!(f, "({})", x));
-                aux(f, l, false, "")
+            Node(ref l, ref x, ref r) => Some((x.clone(), vec![&**l, &**r])),
+            Tip => None
+        }
+    }
+}
+
+impl<T: Clone> Dot for Tree<T> {
+    type Node = Tree<T>;
+
+    fn dot_roots(&self) -> Vec<&Tree<T>> {
+        vec![self]
+    }
+}
+
+// iterates over the elements in order
+pub struct Iter<'a, T: 'a> {
+    stack: Vec<&'a Tree<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, t: &'a Tree<T>) {
+        let mut t = t;
+
+        while let Node(ref l, _, _) = *t {
+            self.stack.push(t);
+            t = l;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.stack.pop() {
+            Some(&Node(_, ref x, ref r)) => {
+                self.push_left(r);
+                Some(x)
             },
-            Tip => Result::Ok(())
+            _ => None
+        }
+    }
+}
+
+impl<T> Tree<T> {
+    pub fn iter(&self) -> Iter<T> {
+        let mut it = Iter { stack: vec![] };
+        it.push_left(self);
+        it
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Tree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// iterates in order over the elements whose key is between the bounds of a range, see `range_by`
+pub struct Range<'a, T: 'a, Q> {
+    iter: Iter<'a, T>,
+    hi: Q,
+    key: fn(&T) -> &Q,
+}
+
+impl<'a, T, Q: Ord> Iterator for Range<'a, T, Q> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.iter.next() {
+            Some(x) if *(self.key)(x) < self.hi => Some(x),
+            _ => {
+                self.iter.stack.clear();
+                None
+            }
+        }
+    }
+}
+
+// The ordered queries compare the elements through a `key` function, so that the map view of a
+// tree can look its bindings up by key alone.
+impl<T> Tree<T> {
+    pub(crate) fn min_elem(&self) -> Option<&T> {
+        let mut t = self;
+        let mut min = None;
+
+        while let Node(ref l, ref v, _) = *t {
+            min = Some(v);
+            t = l;
+        }
+
+        min
+    }
+
+    pub(crate) fn max_elem(&self) -> Option<&T> {
+        let mut t = self;
+        let mut max = None;
+
+        while let Node(_, ref v, ref r) = *t {
+            max = Some(v);
+            t = r;
+        }
+
+        max
+    }
+
+    // the greatest element whose key is smaller than or equal to `x`
+    pub(crate) fn floor_by<Q: Ord>(&self, x: &Q, key: fn(&T) -> &Q) -> Option<&T> {
+        let mut t = self;
+        let mut floor = None;
+
+        while let Node(ref l, ref v, ref r) = *t {
+            match key(v).cmp(x) {
+                Equal => return Some(v),
+                Less => { floor = Some(v); t = r; },
+                Greater => t = l
+            }
+        }
+
+        floor
+    }
+
+    // the smallest element whose key is greater than or equal to `x`
+    pub(crate) fn ceiling_by<Q: Ord>(&self, x: &Q, key: fn(&T) -> &Q) -> Option<&T> {
+        let mut t = self;
+        let mut ceiling = None;
+
+        while let Node(ref l, ref v, ref r) = *t {
+            match key(v).cmp(x) {
+                Equal => return Some(v),
+                Greater => { ceiling = Some(v); t = l; },
+                Less => t = r
+            }
+        }
+
+        ceiling
+    }
+
+    // the elements whose key is in [lo, hi)
+    pub(crate) fn range_by<Q: Ord>(&self, lo: Q, hi: Q, key: fn(&T) -> &Q) -> Range<T, Q> {
+        let mut iter = Iter { stack: vec![] };
+        let mut t = self;
+
+        while let Node(ref l, ref v, ref r) = *t {
+            if *key(v) >= lo {
+                iter.stack.push(t);
+                t = l;
+            } else {
+                t = r;
+            }
         }
+
+        Range { iter: iter, hi: hi, key: key }
+    }
+}
+
+impl<T: Ord> Tree<T> {
+    // builds a tree out of elements given in strictly increasing order in linear time, splitting
+    // them evenly so that the depths of its leaves differ by at most one. The order is only
+    // checked in debug builds, and for the map view the keys have to be distinct as well.
+    pub fn from_sorted_iter<I: IntoIterator<Item=T>>(iter: I) -> Tree<T> {
+        fn build<T, I: Iterator<Item=T>>(n: usize, iter: &mut I) -> Tree<T> {
+            if n == 0 {
+                return Tip;
+            }
+
+            let l = build(n / 2, iter);
+            let x = iter.next().unwrap();
+            let r = build(n - n / 2 - 1, iter);
+
+            Node(Ptr::new(l), x, Ptr::new(r))
+        }
+
+        let xs: Vec<T> = iter.into_iter().collect();
+        debug_assert!(xs.windows(2).all(|w| w[0] < w[1]), "elements are not strictly increasing");
+
+        let n = xs.len();
+        build(n, &mut xs.into_iter())
     }
 }
 
@@ -51,23 +267,219 @@ impl<T: Ord> Tree<T> {
     // Exercise 2.2:
     // only performs at most d + 1 comparisons, where d is the depth of the tree
     pub fn member2(&self, x: T) -> bool {
-        match *self {
-            Tip => false,
-            Node(_, ref v, _) => {
-                fn member_aux<T: Ord>(t: &Tree<T>, x: T, c: &T) -> bool {
-                    match *t {
-                        Tip => x == *c,
-                        Node(ref l, ref v, ref r) =>
-                            if x < *v {
-                                member_aux(l, x, c)
-                            } else {
-                                member_aux(r, x, v)
-                            }
+        let mut t = self;
+        let mut candidate = None;
+
+        while let Node(ref l, ref v, ref r) = *t {
+            if x < *v {
+                t = l;
+            } else {
+                candidate = Some(v);
+                t = r;
+            }
+        }
+
+        match candidate {
+            Some(c) => x == *c,
+            None => false
+        }
+    }
+}
+
+// copies the nodes on `path`, given from the root down together with whether the search went
+// left, on top of the new subtree `t`; everything off the path is shared with the original tree
+pub(crate) fn rebuild<T: Clone>(path: Vec<(&Tree<T>, bool)>, t: Tree<T>) -> Tree<T> {
+    path.into_iter().rev().fold(t, |acc, (n, left)| {
+        match *n {
+            Node(ref l, ref v, ref r) =>
+                if left {
+                    Node(Ptr::new(acc), v.clone(), r.clone())
+                } else {
+                    Node(l.clone(), v.clone(), Ptr::new(acc))
+                },
+            Tip => panic!("empty tree on search path")
+        }
+    })
+}
+
+impl<T: Clone> Tree<T> {
+    // removes the smallest element, returning it together with the remaining tree
+    pub fn remove_min(&self) -> Option<(T, Tree<T>)> {
+        let mut path = vec![];
+        let mut t = self;
+
+        loop {
+            match *t {
+                Tip => return None,
+                Node(ref l, ref v, ref r) =>
+                    match **l {
+                        Tip => return Some((v.clone(), rebuild(path, (**r).clone()))),
+                        _ => {
+                            path.push((t, true));
+                            t = l;
+                        }
                     }
+            }
+        }
+    }
+
+    // joins two trees where every element of `l` is smaller than every element of `r`,
+    // the result shares `l` and all of `r` but its leftmost path
+    pub fn glue(l: &Ptr<Tree<T>>, r: &Ptr<Tree<T>>) -> Tree<T> {
+        match (&**l, &**r) {
+            (&Tip, _) => (**r).clone(),
+            (_, &Tip) => (**l).clone(),
+            _ => {
+                let (m, r1) = r.remove_min().unwrap();
+                Node(l.clone(), m, Ptr::new(r1))
+            }
+        }
+    }
+}
+
+impl<T: Clone> Tree<T> {
+    // Exercise 2.5 (a):
+    // a complete binary tree of depth `d` storing `x` in every node, in O(d) time as both
+    // children of every node are the same shared subtree
+    pub fn complete(x: T, d: usize) -> Tree<T> {
+        let mut t = Tip;
+
+        for _ in 0..d {
+            let s = Ptr::new(t);
+            t = Node(s.clone(), x.clone(), s);
+        }
+
+        t
+    }
+
+    // Exercise 2.5 (b):
+    // a balanced tree of size `n` storing `x` in every node, in O(log n) time; the sizes of the
+    // children of any node differ by at most one
+    pub fn balanced(x: T, n: usize) -> Tree<T> {
+        // the trees of sizes m and m + 1, which share their subtrees
+        fn create2<T: Clone>(x: &T, m: usize) -> (Ptr<Tree<T>>, Ptr<Tree<T>>) {
+            if m == 0 {
+                let tip = Ptr::new(Tip);
+                (tip.clone(), Ptr::new(Node(tip.clone(), x.clone(), tip)))
+            } else if m % 2 == 1 {
+                let (a, b) = create2(x, m / 2);
+                (Ptr::new(Node(a.clone(), x.clone(), a.clone())), Ptr::new(Node(a, x.clone(), b)))
+            } else {
+                let (a, b) = create2(x, m / 2 - 1);
+                (Ptr::new(Node(a, x.clone(), b.clone())), Ptr::new(Node(b.clone(), x.clone(), b)))
+            }
+        }
+
+        (*create2(&x, n).0).clone()
+    }
+}
+
+impl<T: Ord + Clone> Tree<T> {
+    // splits the tree into the elements smaller and bigger than `x`, and whether `x` was in it;
+    // only the nodes on the search path are copied, every subtree hanging off it is shared
+    pub fn split(&self, x: &T) -> (Tree<T>, bool, Tree<T>) {
+        let mut path = vec![];
+        let mut t = self;
+
+        let (mut l, found, mut r) = loop {
+            match *t {
+                Tip => break (Tip, false, Tip),
+                Node(ref l, ref v, _) if *x < *v => { path.push((t, true)); t = l; },
+                Node(_, ref v, ref r) if *x > *v => { path.push((t, false)); t = r; },
+                Node(ref l, _, ref r) => break ((**l).clone(), true, (**r).clone())
+            }
+        };
+
+        for (n, left) in path.into_iter().rev() {
+            if let Node(ref nl, ref v, ref nr) = *n {
+                if left {
+                    r = Node(Ptr::new(r), v.clone(), nr.clone());
+                } else {
+                    l = Node(nl.clone(), v.clone(), Ptr::new(l));
                 }
+            }
+        }
 
-                member_aux(self, x, v)
-            },
+        (l, found, r)
+    }
+}
+
+#[test]
+fn degenerate_tree() {
+    use set::Set;
+
+    let n = 1000000;
+
+    // the shape of inserting 1..n in decreasing order, built directly to avoid the quadratic cost
+    let mut t: Tree<usize> = Tip;
+    for i in 1..n + 1 {
+        t = Node(Ptr::new(t), i, Ptr::new(Tip));
+    }
+
+    assert!(t.member(1));
+    assert!(t.member2(1));
+    assert!(!t.member(0));
+    assert!(!t.member2(0));
+
+    let t2 = t.insert(0).delete(n);
+    assert!(t2.member(0));
+    assert!(!t2.member(n));
+    assert_eq!(t2.iter().count(), n);
+    assert_eq!(t2.remove_min().map(|e| e.0), Some(0));
+
+    // printing it is quadratic in the depth, so only check a shorter one
+    let mut s: Tree<usize> = Tip;
+    for i in 1..1001 {
+        s = Node(Ptr::new(s), i, Ptr::new(Tip));
+    }
+
+    assert_eq!(format!("{}", s).lines().count(), 2001);
+}
+
+#[test]
+fn balanced_trees() {
+    fn depth<T>(t: &Tree<T>) -> usize {
+        match *t {
+            Tip => 0,
+            Node(ref l, _, ref r) => 1 + max(depth(l), depth(r))
         }
     }
+
+    let c = Tree::complete('a', 20);
+    assert_eq!(c.iter().count(), (1 << 20) - 1);
+    assert_eq!(depth(&c), 20);
+    assert_eq!(Tree::complete('a', 0), Tip);
+
+    match c {
+        Node(ref l, _, ref r) => assert!(Ptr::ptr_eq(l, r)),
+        Tip => panic!("missing node")
+    }
+
+    for n in 0..200 {
+        let b = Tree::balanced(0, n);
+        assert_eq!(b.iter().count(), n);
+        assert!(depth(&b) <= 8);
+
+        if let Node(ref l, _, ref r) = b {
+            let (nl, nr) = (l.iter().count(), r.iter().count());
+            assert!(nl == nr || nl == nr + 1 || nl + 1 == nr);
+        }
+    }
+
+    let big = Tree::balanced((), 1 << 40);
+    assert!(big.member2(()));
+
+    let n = 100000;
+    let t = Tree::from_sorted_iter(0..n);
+    assert_eq!(t.iter().cloned().collect::<Vec<usize>>(), (0..n).collect::<Vec<usize>>());
+    assert_eq!(depth(&t), 17);
+    assert_eq!(Tree::from_sorted_iter(0..0), Tip);
+    assert_eq!(Tree::from_sorted_iter(vec![1, 2, 3]),
+               Node(Ptr::new(Node(Ptr::new(Tip), 1, Ptr::new(Tip))), 2, Ptr::new(Node(Ptr::new(Tip), 3, Ptr::new(Tip)))));
+
+    // duplicates or elements out of order would give an invalid search tree
+    if cfg!(debug_assertions) {
+        assert!(::std::panic::catch_unwind(|| Tree::from_sorted_iter(vec![1, 3, 2])).is_err());
+        assert!(::std::panic::catch_unwind(|| Tree::from_sorted_iter(vec![1, 1])).is_err());
+    }
 }
@@ -1,157 +1,329 @@
-use std::fmt::{Debug, Display};
-use std::num::Float;
-use std::rc::Rc;
-
-use tree::Tree;
-use tree::Tree::{Node, Tip};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::iter;
 
 // Tree layout algorithm based on the following paper:
 // "FUNCTIONAL PEARLS - Drawing Trees by Andrew J. Kennedy (1996)"
 // (http://research.microsoft.com/en-us/um/people/akenn/fun/DrawingTrees.pdf)
 
-pub fn pretty_print<T: Clone + Display + Debug>(t: &Tree<(T, f64)>) {
-    fn print_spaces(n: usize) {
-        for _ in (0..n) {
-            print!(" ");
+// Any tree that can be drawn: `node` returns the label and the ordered children of the root, or
+// `None` for an empty tree, such as a `Tip`. Empty children are left out of the layout.
+pub trait LayoutTree {
+    type Label;
+
+    fn node(&self) -> Option<(Self::Label, Vec<&Self>)>;
+}
+
+// A tree with the horizontal position of every node, which `design` gives relative to the parent
+// and `absolute` turns into an absolute one. Nodes one level apart are one unit apart vertically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout<T> {
+    pub label: T,
+    pub x: f64,
+    pub children: Vec<Layout<T>>,
+}
+
+pub fn move_by_leftmost<T: Clone>(t: &Layout<T>) -> Layout<T> {
+    fn move_by_offset<T: Clone>(t: &Layout<T>, o: f64) -> Layout<T> {
+        Layout {
+            label: t.label.clone(),
+            x: t.x + o,
+            children: t.children.iter().map(|c| move_by_offset(c, o)).collect()
         }
     }
 
-    fn aux<T: Clone + Display + Debug>(current: Vec<Tree<(T, f64)>>, mut next: Vec<Tree<(T, f64)>>, offset: f64) {
-        // FIXME: does not take into account displacement due to the size of the printed node value
-        let factor = 1.0; // FIXME: hardcoded offset factor
-
-        match current.first() {
-            None =>
-                if !next.is_empty() {
-                    println!("");
-                    print!("   "); // FIXME: hardcoded offset
-                    aux(next, vec![], 0.0);
-                },
-            Some(t) => {
-                let o =
-                    match *t {
-                    Tip => offset,
-                    Node(ref l, (ref v, x), ref r) => {
-                        let sp = x - offset;
-                        print_spaces((sp * factor) as usize);
-
-                        print!("{}", v);
-                        next.push((**l).clone());
-                        next.push((**r).clone());
-
-                        sp
-                    }
-                    };
-                aux(current.tail().to_vec(), next, o);
-            }
-        }
+    fn find_leftmost<T>(t: &Layout<T>, current: f64) -> f64 {
+        let n = t.x.min(current);
+        t.children.iter().fold(n, |m, c| find_leftmost(c, n).min(m))
     }
 
-    aux(vec![], vec![(*t).clone()], 0.0);
+    move_by_offset(t, -find_leftmost(t, 0.0))
 }
 
-pub fn move_by_leftmost<T: Clone>(t: &Tree<(T, f64)>) -> Tree<(T, f64)> {
-    fn move_by_offset<T: Clone>(t: &Tree<(T, f64)>, o: f64) -> Tree<(T, f64)> {
-        match *t {
-            Tip => Tip,
-            Node(ref l, (ref v, x), ref r) => {
-                Node(Rc::new(move_by_offset(&*l, o)), (v.clone(), x + o), Rc::new(move_by_offset(&*r, o)))
-            }
-        }
+pub fn absolute<T: Clone>(t: &Layout<T>) -> Layout<T> {
+    fn aux<T: Clone>(t: &Layout<T>, d: f64) -> Layout<T> {
+        let a = d + t.x;
+        Layout { label: t.label.clone(), x: a, children: t.children.iter().map(|c| aux(c, a)).collect() }
     }
 
-    fn find_leftmost<T>(t: &Tree<(T, f64)>, current: f64) -> f64 {
-        match *t {
-            Tip => current,
-            Node(ref l, (_, x), ref r) => {
-                let n = x.min(current);
-                find_leftmost(l, n).min(find_leftmost(r, n))
-            }
+    aux(t, 0.0)
+}
+
+pub fn design<L: LayoutTree>(t: &L) -> Option<Layout<L::Label>> {
+    design_by(t, |_| 0.0, 1.0)
+}
+
+// Like `design`, for labels that take some room: `width` gives the width of every label, in units
+// of the layout, and neighbouring labels are kept at least `gap` apart instead of one unit.
+pub fn design_by<L: LayoutTree, F: Fn(&L::Label) -> f64>(t: &L, width: F, gap: f64) -> Option<Layout<L::Label>> {
+    fn aux<L: LayoutTree, F: Fn(&L::Label) -> f64>(t: &L, width: &F, gap: f64) -> Option<(Layout<L::Label>, Extent)> {
+        t.node().map(|(v, children)| {
+            let (trees, extents): (Vec<Layout<L::Label>>, Vec<Extent>) =
+                children.into_iter().filter_map(|c| aux(c, width, gap)).unzip();
+            let positions = fit_list(extents.clone(), gap);
+            let ptrees: Vec<Layout<L::Label>> = trees.into_iter().zip(positions.iter()).map(|f| {
+                let (t, x) = f;
+                move_tree(t, *x)
+            }).collect();
+            let pextents = extents.into_iter().zip(positions.iter()).map(|f| {
+                let (e, x) = f;
+                move_extent(e, *x)
+            }).collect();
+
+            let w = width(&v) / 2.0;
+            let mut resultextent = merge_extents(pextents);
+            resultextent.insert(0, (-w, w));
+            let resulttree = Layout { label: v, x: 0.0, children: ptrees };
+
+            (resulttree, resultextent)
+        })
+    }
+
+    aux(t, &width, gap).map(|r| r.0)
+}
+
+// (label, absolute position, depth, index of the parent) of every node, in preorder
+fn flatten<T: Display>(t: &Layout<T>) -> Vec<(String, f64, usize, Option<usize>)> {
+    fn aux<T: Display>(t: &Layout<T>, d: f64, depth: usize, parent: Option<usize>,
+                       nodes: &mut Vec<(String, f64, usize, Option<usize>)>) {
+        let i = nodes.len();
+        nodes.push((format!("{}", t.label), d + t.x, depth, parent));
+
+        for c in &t.children {
+            aux(c, d + t.x, depth + 1, Some(i), nodes);
         }
     }
 
-    move_by_offset(t, -find_leftmost(t, 0.0))
+    let mut nodes = vec![];
+    aux(t, 0.0, 0, None, &mut nodes);
+    nodes
 }
 
-pub fn absolute_new<T: Clone + Display>(t: &Tree<(T, f64)>) -> Tree<(T, f64)> {
-    fn aux<T: Clone + Display>(t: &Tree<(T, f64)>, d: f64, vd: f64) -> Tree<(T, f64)> {
-        match *t {
-            Tip => Tip,
-            Node(ref l, (ref v, x), ref r) => {
-                let a = d + x;
-                let d = a + (a.signum() * vd);
-                let nvd = vd + (format!("{}", v).len() as f64);
-                println!("nvd: {}", nvd);
-                Node(Rc::new(aux(&*l, a, nvd)), (v.clone(), d), Rc::new(aux(&*r, a, nvd)))
+// (index among the children of its parent, number of children of the parent) of every node, in
+// the preorder of `flatten`; the empty children that the layout leaves out are counted, so that a
+// lone child of a binary tree is still known to be a left or a right one
+fn slots<L: LayoutTree>(t: &L) -> Vec<Option<(usize, usize)>> {
+    fn aux<L: LayoutTree>(t: &L, slot: Option<(usize, usize)>, slots: &mut Vec<Option<(usize, usize)>>) {
+        if let Some((_, children)) = t.node() {
+            slots.push(slot);
+
+            let n = children.len();
+
+            for (j, c) in children.into_iter().enumerate() {
+                aux(c, Some((j, n)), slots);
             }
         }
     }
 
-    aux(t, 0.0, 0.0)
+    let mut slots = vec![];
+    aux(t, None, &mut slots);
+    slots
 }
 
-pub fn absolute<T: Clone + Display>(t: &Tree<(T, f64)>) -> Tree<(T, f64)> {
-    fn aux<T: Clone + Display>(t: &Tree<(T, f64)>, d: f64) -> Tree<(T, f64)> {
-        match *t {
-            Tip => Tip,
-            Node(ref l, (ref v, x), ref r) => {
-                let a = d + x;
-                Node(Rc::new(aux(&*l, a)), (v.clone(), a), Rc::new(aux(&*r, a)))
-            }
+// Dimensions of the SVG drawing, in pixels. Labels are drawn in a monospace font, so their width
+// is estimated from the number of chars.
+const SVG_FONT_SIZE: f64 = 14.0;
+const SVG_CHAR_WIDTH: f64 = 8.4;
+const SVG_PADDING: f64 = 6.0;
+const SVG_BOX_HEIGHT: f64 = 24.0;
+const SVG_LEVEL_HEIGHT: f64 = 64.0;
+const SVG_GAP: f64 = 12.0;
+const SVG_MARGIN: f64 = 10.0;
+
+fn escape_xml(s: &str) -> String {
+    s.chars().fold(String::new(), |mut acc, c| {
+        match c {
+            '&' => acc.push_str("&amp;"),
+            '<' => acc.push_str("&lt;"),
+            '>' => acc.push_str("&gt;"),
+            '"' => acc.push_str("&quot;"),
+            '\'' => acc.push_str("&apos;"),
+            c => acc.push(c)
         }
+
+        acc
+    })
+}
+
+// Draws a layout returned by `design` as an SVG image: every node is a box sized to its label, at
+// the height of its depth, and a line joins it to each of its children. One unit of the layout is
+// as wide as the widest box plus a gap, which is enough to keep the boxes of a level apart.
+pub fn write_svg<T: Display, W: Write>(t: &Layout<T>, w: &mut W) -> io::Result<()> {
+    fn box_width(label: &str) -> f64 {
+        label.chars().count() as f64 * SVG_CHAR_WIDTH + 2.0 * SVG_PADDING
     }
 
-    aux(t, 0.0)
+    let nodes = flatten(t);
+
+    let widest = nodes.iter().map(|n| box_width(&n.0)).fold(0.0, f64::max);
+    let unit = widest + SVG_GAP;
+    let leftmost = nodes.iter().map(|n| n.1).fold(f64::INFINITY, f64::min);
+    let rightmost = nodes.iter().map(|n| n.1).fold(f64::NEG_INFINITY, f64::max);
+    let depth = nodes.iter().map(|n| n.2).max().unwrap_or(0);
+
+    // the center of the top of every box
+    let center = |n: &(String, f64, usize, Option<usize>)| {
+        (SVG_MARGIN + widest / 2.0 + (n.1 - leftmost) * unit, SVG_MARGIN + n.2 as f64 * SVG_LEVEL_HEIGHT)
+    };
+
+    let width = 2.0 * SVG_MARGIN + widest + (rightmost - leftmost) * unit;
+    let height = 2.0 * SVG_MARGIN + SVG_BOX_HEIGHT + depth as f64 * SVG_LEVEL_HEIGHT;
+
+    try!(writeln!(w, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+                  width, height, width, height));
+
+    try!(writeln!(w, "<g stroke=\"black\">"));
+
+    for n in &nodes {
+        if let Some(p) = n.3 {
+            let (x1, y1) = center(&nodes[p]);
+            let (x2, y2) = center(n);
+            try!(writeln!(w, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>", x1, y1 + SVG_BOX_HEIGHT, x2, y2));
+        }
+    }
+
+    try!(writeln!(w, "</g>"));
+    try!(writeln!(w, "<g font-family=\"monospace\" font-size=\"{}\" text-anchor=\"middle\">", SVG_FONT_SIZE));
+
+    for n in &nodes {
+        let (x, y) = center(n);
+        let bw = box_width(&n.0);
+
+        try!(writeln!(w, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\" stroke=\"black\"/>",
+                      x - bw / 2.0, y, bw, SVG_BOX_HEIGHT));
+        try!(writeln!(w, "<text x=\"{}\" y=\"{}\" dominant-baseline=\"central\">{}</text>",
+                      x, y + SVG_BOX_HEIGHT / 2.0, escape_xml(&n.0)));
+    }
+
+    try!(writeln!(w, "</g>"));
+    writeln!(w, "</svg>")
 }
 
-pub fn design<T: Clone>(t: &Tree<T>) -> Tree<(T, f64)> {
-    fn aux<T: Clone>(t: &Tree<T>) -> (Tree<(T, f64)>, Extent) {
-        match *t {
-            Tip => (Tip, vec![]),
-            Node(ref l, ref v, ref r) => {
-                let (trees, extents): (Vec<Tree<(T, f64)>>, Vec<Extent>) = vec![aux(l), aux(r)].into_iter().unzip();
-                let positions = fit_list(extents.clone());
-                let ptrees: Vec<Tree<(T, f64)>> = trees.iter().zip(positions.iter()).map(|f| {
-                    let (t, x) = f;
-                    move_tree(t, *x)
-                }).collect();
-                let pextents = extents.into_iter().zip(positions.iter()).map(|f| {
-                    let (e, x) = f;
-                    move_extent(e, *x)
-                }).collect();
-
-                let mut resultextent = merge_extents(pextents);
-                resultextent.insert(0, (0.0, 0.0));
-                let resulttree = Node(Rc::new(ptrees[0].clone()), ((*v).clone(), 0.0), Rc::new(ptrees[1].clone()));
-
-                (resulttree, resultextent)
+pub fn to_svg<T: Display>(t: &Layout<T>) -> String {
+    let mut buf = vec![];
+    write_svg(t, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+// Settings of the ASCII drawing: `spacing` stretches the layout horizontally, and is taken as 1.0
+// when it is less, `min_gap` is the least number of columns between two labels of a level, and
+// `left`, `right` and `straight` join a node to its first child, to its last one and to any other,
+// or to an only child.
+#[derive(Clone, Debug)]
+pub struct AsciiConfig {
+    pub spacing: f64,
+    pub min_gap: usize,
+    pub left: char,
+    pub right: char,
+    pub straight: char,
+}
+
+impl Default for AsciiConfig {
+    fn default() -> AsciiConfig {
+        AsciiConfig { spacing: 1.0, min_gap: 2, left: '/', right: '\\', straight: '|' }
+    }
+}
+
+// the number of columns a string takes in a terminal, where wide East Asian chars and emoji take
+// two and combining marks none
+fn display_width(s: &str) -> usize {
+    s.chars().map(|c| match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F => 0,
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F |
+        0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F | 0x20000..=0x3FFFD => 2,
+        _ => 1
+    }).sum()
+}
+
+// Draws a tree as text, a line of labels per level with a line of connectors between levels. The
+// nodes are laid out by `design_by` with the display width of their labels, in columns, so that
+// labels of any width are kept `min_gap` columns apart. It writes to a `fmt::Write`, like a
+// `String` or a `Formatter`, since the drawing is text.
+pub fn write_ascii<L: LayoutTree, W: fmt::Write>(t: &L, config: &AsciiConfig, w: &mut W) -> fmt::Result
+    where L::Label: Display {
+    let layout = match design_by(t, |l| display_width(&format!("{}", l)) as f64, config.min_gap as f64) {
+        Some(layout) => layout,
+        None => return Result::Ok(())
+    };
+
+    let nodes = flatten(&layout);
+    let slots = slots(t);
+    let widths: Vec<usize> = nodes.iter().map(|n| display_width(&n.0)).collect();
+    let spacing = config.spacing.max(1.0);
+
+    // labels are shifted so that the leftmost one starts at column 0 before they are rounded to
+    // columns, which keeps rounding the same on both sides of the root
+    let starts: Vec<f64> = nodes.iter().zip(widths.iter()).map(|(n, &w)| n.1 * spacing - w as f64 / 2.0).collect();
+    let first = starts.iter().cloned().fold(f64::INFINITY, f64::min);
+    let columns: Vec<usize> = starts.iter().map(|s| (s - first).round() as usize).collect();
+    let center = |i: usize| columns[i] + widths[i].saturating_sub(1) / 2;
+
+    // the pieces of every line with their columns, the labels of depth `d` going to line `2 * d`
+    let depth = nodes.iter().map(|n| n.2).max().unwrap_or(0);
+    let mut lines: Vec<Vec<(usize, String)>> = vec![vec![]; 2 * depth + 1];
+
+    for (i, n) in nodes.iter().enumerate() {
+        lines[2 * n.2].push((columns[i], n.0.clone()));
+
+        // the side of the child is taken from its place among the children of its parent, since
+        // a lone child is laid out right below it whichever side it is on
+        if let (Some(p), Some((j, m))) = (n.3, slots[i]) {
+            let (pc, cc) = (center(p), center(i));
+            let (column, c) =
+                if m > 1 && j == 0 { ((pc + cc) / 2, config.left) }
+                else if m > 1 && j == m - 1 { ((pc + cc + 1) / 2, config.right) }
+                else { (cc, config.straight) };
+
+            lines[2 * n.2 - 1].push((column, c.to_string()));
+        }
+    }
+
+    for mut pieces in lines {
+        pieces.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut line = String::new();
+        let mut column = 0;
+
+        for (c, piece) in pieces {
+            // the connectors of two children can fall on the same column
+            if c < column {
+                continue;
             }
+
+            line.extend(iter::repeat(' ').take(c - column));
+            line.push_str(&piece);
+            column = c + display_width(&piece);
         }
 
+        try!(writeln!(w, "{}", line));
     }
-    aux(t).0
+
+    Result::Ok(())
 }
 
-fn move_tree<T: Clone>(t: &Tree<(T, f64)>, x1: f64) -> Tree<(T, f64)> {
-    match *t {
-        Tip => Tip,
-        Node(ref l, (ref v, x), ref r) => Node(l.clone(), (v.clone(), x + x1), r.clone())
-    }
+pub fn to_ascii<L: LayoutTree>(t: &L, config: &AsciiConfig) -> String where L::Label: Display {
+    let mut s = String::new();
+    write_ascii(t, config, &mut s).unwrap();
+    s
+}
+
+fn move_tree<T>(t: Layout<T>, x1: f64) -> Layout<T> {
+    Layout { x: t.x + x1, ..t }
 }
 
 type Extent = Vec<(f64, f64)>;
 
-fn fit_list(es: Vec<Extent>) -> Vec<f64> {
+fn fit_list(es: Vec<Extent>, gap: f64) -> Vec<f64> {
     fn mean(x: f64, y: f64) -> f64 {
         (x + y) / 2.0
     }
 
-    fit_list_left(es.clone()).iter().zip(
-        fit_list_right(es.clone()).iter())
+    fit_list_left(es.clone(), gap).iter().zip(
+        fit_list_right(es.clone(), gap).iter())
         .map(|x| mean(*x.0, *x.1)).collect()
 }
 
-fn fit_list_right(es: Vec<Extent>) -> Vec<f64> {
+fn fit_list_right(es: Vec<Extent>, gap: f64) -> Vec<f64> {
     fn flip_extent(e: Extent) -> Extent {
         e.iter().map(|&x| {
             let (p, q) = x;
@@ -160,33 +332,33 @@ fn fit_list_right(es: Vec<Extent>) -> Vec<f64> {
     }
 
     fit_list_left(
-        es.iter().rev().map(|e| flip_extent((*e).clone())).collect()).iter()
+        es.iter().rev().map(|e| flip_extent((*e).clone())).collect(), gap).iter()
         .map(|&f| -f).rev().collect()
 }
 
-fn fit_list_left(es: Vec<Extent>) -> Vec<f64> {
-    fn aux(es: Vec<Extent>, acc: Extent) -> Vec<f64> {
+fn fit_list_left(es: Vec<Extent>, gap: f64) -> Vec<f64> {
+    fn aux(es: Vec<Extent>, acc: Extent, gap: f64) -> Vec<f64> {
         match es.first() {
             Some(e) => {
-                let x = fit(acc.clone(), e.clone());
-                let mut r = aux(es.tail().to_vec(), merge_extent(acc.clone(), move_extent(e.clone().to_vec(), x)));
+                let x = fit(acc.clone(), e.clone(), gap);
+                let mut r = aux(es[1..].to_vec(), merge_extent(acc.clone(), move_extent(e.clone().to_vec(), x)), gap);
                 r.insert(0, x);
                 r
             },
             None => vec![]
         }
     }
-    aux(es, vec![])
+    aux(es, vec![], gap)
 }
 
 fn rmax(p: f64, q: f64) -> f64 {
     if p > q { p } else { q }
 }
 
-fn fit(e1: Extent, e2: Extent) -> f64 {
+fn fit(e1: Extent, e2: Extent, gap: f64) -> f64 {
     match (e1.first(), e2.first()) {
         (Some(&(_, p)), Some(&(q, _))) =>
-            rmax(fit(e1.tail().to_vec(), e2.tail().to_vec()), p - q + 1.0),
+            rmax(fit(e1[1..].to_vec(), e2[1..].to_vec(), gap), p - q + gap),
         (_, _) => 0.0
     }
 }
@@ -196,7 +368,7 @@ fn merge_extent(e1: Extent, e2: Extent) -> Extent {
         (None, _) => e2,
         (_, None) => e1,
         (Some(&(p, _)), Some(&(_, q))) => {
-            let mut m = merge_extent(e1.tail().to_vec(), e2.tail().to_vec());
+            let mut m = merge_extent(e1[1..].to_vec(), e2[1..].to_vec());
             m.insert(0, (p, q));
             m
         }
@@ -215,3 +387,110 @@ fn move_extent(e: Extent, x: f64) -> Extent {
         (p + x, q + x)
     }).collect()
 }
+
+#[test]
+fn design_layout() {
+    use set::Set;
+    use tree::Tree;
+    use trie::PatriciaTrie;
+
+    fn xs<T: Clone>(t: &Layout<T>) -> Vec<(T, f64)> {
+        let mut v = vec![(t.label.clone(), t.x)];
+
+        for c in &t.children {
+            v.extend(xs(c));
+        }
+
+        v
+    }
+
+    let t: Tree<usize> = Set::empty();
+    assert_eq!(design(&t), None);
+
+    let l = design(&t.insert(2).insert(1).insert(3)).unwrap();
+    assert_eq!(xs(&l), vec![(2, 0.0), (1, -0.5), (3, 0.5)]);
+
+    // a lone child stays under its parent, on either side
+    let l = design(&t.insert(2).insert(1).insert(0)).unwrap();
+    assert_eq!(xs(&absolute(&l)), vec![(2, 0.0), (1, 0.0), (0, 0.0)]);
+
+    // subtrees are pushed apart by their widest level, not by their roots
+    let l = design(&t.insert(4).insert(2).insert(1).insert(3).insert(6).insert(5).insert(7)).unwrap();
+    assert_eq!(xs(&move_by_leftmost(&absolute(&l))),
+               vec![(4, 1.5), (2, 0.5), (1, 0.0), (3, 1.0), (6, 2.5), (5, 2.0), (7, 3.0)]);
+
+    let trie: PatriciaTrie<usize> = vec![("a", 0), ("b", 1), ("c", 2), ("cd", 3)]
+        .into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    let l = design(&trie).unwrap();
+    assert_eq!(xs(&move_by_leftmost(&absolute(&l))),
+               vec![("".to_string(), 1.0), ("a => (0)".to_string(), 0.0), ("b => (1)".to_string(), 1.0),
+                    ("c => (2)".to_string(), 2.0), ("d => (3)".to_string(), 2.0)]);
+}
+
+#[test]
+fn svg() {
+    use set::Set;
+    use tree::Tree;
+
+    let t: Tree<&str> = Set::empty();
+    let l = design(&t.insert("b").insert("a<").insert("wide label")).unwrap();
+    let svg = to_svg(&l);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<rect").count(), 3);
+    assert_eq!(svg.matches("<line").count(), 2);
+    assert!(svg.contains(">a&lt;</text>"));
+
+    // the boxes fit their labels, and the unit is the widest box plus the gap
+    let wide = 10.0 * SVG_CHAR_WIDTH + 2.0 * SVG_PADDING;
+    assert!(svg.contains(&format!("width=\"{}\" height=\"{}\" fill", wide, SVG_BOX_HEIGHT)));
+    assert!(svg.contains(&format!("width=\"{}\" height=\"{}\" fill", 1.0 * SVG_CHAR_WIDTH + 2.0 * SVG_PADDING, SVG_BOX_HEIGHT)));
+    assert!(svg.contains(&format!("width=\"{}\" height=\"{}\"", 2.0 * SVG_MARGIN + 2.0 * wide + SVG_GAP,
+                                  2.0 * SVG_MARGIN + SVG_BOX_HEIGHT + SVG_LEVEL_HEIGHT)));
+}
+
+#[test]
+fn ascii() {
+    use heap::PairingHeap;
+    use set::Set;
+    use tree::Tree;
+
+    let t: Tree<usize> = Set::empty();
+    let t2 = vec![4, 2, 6, 1, 3, 5, 7].into_iter().fold(t.clone(), |t, x| t.insert(x));
+
+    assert_eq!(to_ascii(&t, &AsciiConfig::default()), "");
+    assert_eq!(to_ascii(&t2, &AsciiConfig::default()), concat!(
+        "     4\n",
+        "   /   \\\n",
+        "  2     6\n",
+        " / \\   / \\\n",
+        "1  3  5  7\n"));
+
+    let config = AsciiConfig { spacing: 2.0, min_gap: 1, left: '.', right: '.', straight: ':' };
+    assert_eq!(to_ascii(&t.insert(1).insert(0).insert(2), &config), concat!(
+        "  1\n",
+        " . .\n",
+        "0   2\n"));
+
+    // wide labels push their subtrees apart instead of overlapping
+    let t3 = vec!["b", "a<", "wide label", "c"].into_iter().fold(Set::empty(), |t: Tree<&str>, x| t.insert(x));
+    assert_eq!(to_ascii(&t3, &AsciiConfig::default()), concat!(
+        "     b\n",
+        "  /    \\\n",
+        "a<  wide label\n",
+        "        /\n",
+        "         c\n"));
+
+    // a spacing below 1.0 would let labels overlap
+    let config = AsciiConfig { spacing: 0.5, ..AsciiConfig::default() };
+    assert_eq!(to_ascii(&t2, &config), to_ascii(&t2, &AsciiConfig::default()));
+
+    let l: PairingHeap<usize> = vec![1, 0].into_iter().collect();
+    assert_eq!(to_ascii(&l, &AsciiConfig::default()), "0\n|\n1\n");
+
+    let t4 = vec!["\u{65e5}\u{672c}", "\u{3042}", "\u{8a9e}"].into_iter().fold(Set::empty(), |t: Tree<&str>, x| t.insert(x));
+    for line in to_ascii(&t4, &AsciiConfig::default()).lines() {
+        assert!(display_width(line) <= 9);
+    }
+}
@@ -1,128 +1,475 @@
-use std::collections::HashMap;
-use std::fmt::{Display, Error, Formatter};
-use std::rc::Rc;
+use std::ascii;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::iter::FromIterator;
+use std::ops::Deref;
 
+use dot::Dot;
 use map::Map;
+use ptr::Ptr;
+use tree_layout::LayoutTree;
 
-#[derive(Clone, Debug)]
-pub enum PatriciaTrie<T> {
+// The keys of a `Trie`, which are only split between its nodes at the boundaries of their units:
+// the chars of a `str`, so that every node holds a valid string, or the bytes of a `[u8]`.
+pub trait Key: PartialEq {
+    type Owned: Clone + Debug + Deref<Target=Self>;
+    type Unit: Ord + Copy + Debug;
+
+    fn empty_key() -> Self::Owned;
+    fn to_key(&self) -> Self::Owned;
+    fn push_key(&mut Self::Owned, &Self);
+
+    fn len(&self) -> usize;
+    fn split_at(&self, usize) -> (&Self, &Self);
+    fn first_unit(&self) -> Option<Self::Unit>;
+
+    // the length of the longest common prefix, which ends on a unit boundary of both keys
+    fn common_prefix_len(&self, &Self) -> usize;
+
+    // how a node shows its part of a key, in `Display` and the layouts
+    fn label(&self) -> String;
+
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self.common_prefix_len(prefix) == prefix.len()
+    }
+}
+
+impl Key for str {
+    type Owned = String;
+    type Unit = char;
+
+    fn empty_key() -> String {
+        String::new()
+    }
+
+    fn to_key(&self) -> String {
+        self.to_string()
+    }
+
+    fn push_key(key: &mut String, k: &str) {
+        key.push_str(k)
+    }
+
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    fn split_at(&self, i: usize) -> (&str, &str) {
+        str::split_at(self, i)
+    }
+
+    fn first_unit(&self) -> Option<char> {
+        self.chars().next()
+    }
+
+    // in bytes, so that it can be used to slice both strings
+    fn common_prefix_len(&self, k: &str) -> usize {
+        self.chars().zip(k.chars()).take_while(|t| t.0 == t.1).map(|t| t.0.len_utf8()).sum()
+    }
+
+    fn label(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Key for [u8] {
+    type Owned = Vec<u8>;
+    type Unit = u8;
+
+    fn empty_key() -> Vec<u8> {
+        vec![]
+    }
+
+    fn to_key(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn push_key(key: &mut Vec<u8>, k: &[u8]) {
+        key.extend_from_slice(k)
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn split_at(&self, i: usize) -> (&[u8], &[u8]) {
+        <[u8]>::split_at(self, i)
+    }
+
+    fn first_unit(&self) -> Option<u8> {
+        self.first().cloned()
+    }
+
+    fn common_prefix_len(&self, k: &[u8]) -> usize {
+        self.iter().zip(k.iter()).take_while(|t| t.0 == t.1).count()
+    }
+
+    // escaped like in byte string literals, since a node may hold only part of a UTF-8 char
+    fn label(&self) -> String {
+        self.iter().flat_map(|b| ascii::escape_default(*b)).map(|b| b as char).collect()
+    }
+}
+
+// A Patricia trie whose keys are split at the units of `K`. Every node below the root holds the
+// part of its key that follows its parent's, and is filed under the first unit of that part.
+pub enum Trie<K: ?Sized + Key, T> {
     Tip,
-    Node { key: String, value: Option<T>, children: HashMap<char, Rc<PatriciaTrie<T>>> }
+    Node { key: K::Owned, value: Option<T>, children: BTreeMap<K::Unit, Ptr<Trie<K, T>>> }
 }
 
-use trie::PatriciaTrie::{Tip, Node};
+use trie::Trie::{Tip, Node};
 
-fn longest_common_prefix(s1: &str, s2: &str) -> usize {
-    s1.chars().zip(s2.chars()).take_while(|t| t.0 == t.1).count()
+// a trie over strings, which are only split between chars
+pub type PatriciaTrie<T> = Trie<str, T>;
+
+// A Patricia trie over bytes, for keys that aren't strings. Any key that can be viewed as a byte
+// slice can be bound, and the keys are given back as vectors in lexicographic order of the bytes,
+// which for UTF-8 strings is the same as the order of their chars.
+pub type ByteTrie<T> = Trie<[u8], T>;
+
+impl<K: ?Sized + Key, T: Clone> Clone for Trie<K, T> {
+    fn clone(&self) -> Trie<K, T> {
+        match *self {
+            Tip => Tip,
+            Node { ref key, ref value, ref children } =>
+                Node { key: key.clone(), value: value.clone(), children: children.clone() }
+        }
+    }
+}
+
+impl<K: ?Sized + Key, T: Debug> Debug for Trie<K, T> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            Tip => write!(f, "Tip"),
+            Node { ref key, ref value, ref children } =>
+                f.debug_struct("Node").field("key", key).field("value", value).field("children", children).finish()
+        }
+    }
 }
 
-// taken from: http://stackoverflow.com/questions/28392008/more-concise-hashmap-initialization
-macro_rules! hashmap {
+// adapted from: http://stackoverflow.com/questions/28392008/more-concise-hashmap-initialization
+macro_rules! btreemap {
     ($( $key: expr => $val: expr ),*) => {{
-         let mut map = ::std::collections::HashMap::new();
+         let mut map = ::std::collections::BTreeMap::new();
          $( map.insert($key, $val); )*
          map
     }}
 }
 
-impl<T: Clone> Map<String, T> for PatriciaTrie<T> {
-    fn empty() -> PatriciaTrie<T> {
-        Tip
-    }
+fn first_unit<K: ?Sized + Key>(k: &K) -> K::Unit {
+    k.first_unit().unwrap()
+}
 
-    fn bind(&self, k: String, v: T) -> PatriciaTrie<T> {
-        fn add_children<T: Clone>(t: &PatriciaTrie<T>, k: String, v: T) -> PatriciaTrie<T> {
-            match *t {
-                Tip => panic!("undefined"),
-                Node { ref key, ref value, ref children } =>
-                    match children.get(&k.char_at(0)) {
-                        Some(n) => n.bind(k, v),
-                        None => Node { key: k, value: Some(v), children: hashmap![] }
-                    }
+fn bind_key<K: ?Sized + Key, T: Clone>(t: &Trie<K, T>, k: &K, v: T) -> Trie<K, T> {
+    match *t {
+        Tip => Node { key: k.to_key(), value: Some(v), children: btreemap![] },
+        Node { ref key, ref value, ref children } => {
+            let i = k.common_prefix_len(key);
+
+            // update an already existing key
+            if i == k.len() && i == key.len() {
+                Node { key: key.clone(), value: Some(v), children: children.clone() }
+            }
+            // the existing key is contained in the new key
+            else if i == key.len() {
+                let k1 = k.split_at(i).1;
+
+                let c = match children.get(&first_unit(k1)) {
+                    Some(c) => bind_key(c, k1, v),
+                    None => Node { key: k1.to_key(), value: Some(v), children: btreemap![] }
+                };
+
+                let mut children = children.clone();
+                children.insert(first_unit(k1), Ptr::new(c));
+
+                Node { key: key.clone(), value: value.clone(), children: children }
+            }
+            // the new key is contained in the existing key
+            else if i == k.len() {
+                let k1 = key.split_at(i).1;
+                let children = btreemap![
+                    first_unit(k1) => Ptr::new(Node { key: k1.to_key(), value: value.clone(), children: children.clone() })];
+                Node { key: k.to_key(), value: Some(v), children: children }
+            }
+            // split at longest common prefix
+            else {
+                let (common, k2) = k.split_at(i);
+                let k1 = key.split_at(i).1;
+
+                let children = btreemap![
+                    first_unit(k1) => Ptr::new(Node { key: k1.to_key(), value: value.clone(), children: children.clone() }),
+                    first_unit(k2) => Ptr::new(Node { key: k2.to_key(), value: Some(v), children: btreemap![] })];
+
+                Node { key: common.to_key(), value: None, children: children }
             }
         }
+    }
+}
 
-        match *self {
-            Tip => Node { key: k, value: Some(v), children: hashmap![] },
+fn get_key<'a, K: ?Sized + Key, T>(t: &'a Trie<K, T>, k: &K) -> Option<&'a T> {
+    let mut t = t;
+    let mut k = k;
+
+    loop {
+        match *t {
+            Tip => return None,
             Node { ref key, ref value, ref children } => {
-                let i = longest_common_prefix(&k, &key);
+                if !k.has_prefix(key) {
+                    return None;
+                }
 
-                // update an already existing key
-                if i == k.len() && i == key.len() {
-                    Node { key: k, value: Some(v), children: children.clone() }
+                k = k.split_at(key.len()).1;
+
+                match k.first_unit() {
+                    None => return value.as_ref(),
+                    Some(u) =>
+                        match children.get(&u) {
+                            Some(c) => t = c,
+                            None => return None
+                        }
                 }
-                // the existing key is contained in the new key
-                else if i == key.len() {
-                    let k1 = &k[i..];
+            }
+        }
+    }
+}
 
-                    let mut children = children.clone();
-                    children.insert(k1.char_at(0), Rc::new(add_children(self, k1.to_string(), v)));
+// `k` has to be bound in `t`
+fn unbind_key<K: ?Sized + Key, T: Clone>(t: &Trie<K, T>, k: &K) -> Trie<K, T> {
+    // rebuilds a node so that the trie stays compressed: a node without a value is removed if
+    // it has no children and merged with its child if it only has one
+    fn compress<K: ?Sized + Key, T: Clone>(key: K::Owned, value: Option<T>, children: BTreeMap<K::Unit, Ptr<Trie<K, T>>>) -> Trie<K, T> {
+        if value.is_some() || children.len() > 1 {
+            return Node { key: key, value: value, children: children };
+        }
 
-                    Node { key: key.clone(), value: value.clone(), children: children }
+        match children.values().next() {
+            None => Tip,
+            Some(c) =>
+                match **c {
+                    Node { key: ref k1, value: ref v1, children: ref c1 } => {
+                        let mut key = key;
+                        K::push_key(&mut key, k1);
+                        Node { key: key, value: v1.clone(), children: c1.clone() }
+                    },
+                    Tip => panic!("undefined")
                 }
-                // the new key is contained in the existing key
-                else if i == k.len() {
-                    let k1 = &key[i..];
-                    let children = hashmap![
-                        k1.char_at(0) => Rc::new(Node { key: k1.to_string(), value: value.clone(), children: children.clone() })];
-                    Node { key: k, value: Some(v), children: children }
+        }
+    }
+
+    match *t {
+        Tip => Tip,
+        Node { ref key, ref value, ref children } => {
+            let k1 = k.split_at(key.len()).1;
+
+            match k1.first_unit() {
+                None => compress(key.clone(), None, children.clone()),
+                Some(u) => {
+                    let mut children = children.clone();
+
+                    match unbind_key(&children[&u], k1) {
+                        Tip => { children.remove(&u); },
+                        n => { children.insert(u, Ptr::new(n)); }
+                    }
+
+                    compress(key.clone(), value.clone(), children)
                 }
-                // split at longest common prefix
-                else {
-                    let common = &k[..i];
+            }
+        }
+    }
+}
+
+impl<T: Clone> Map<String, T> for PatriciaTrie<T> {
+    fn empty() -> PatriciaTrie<T> {
+        Tip
+    }
+
+    fn bind(&self, k: String, v: T) -> PatriciaTrie<T> {
+        bind_key(self, k.as_str(), v)
+    }
+
+    fn get(&self, k: String) -> Option<T> {
+        get_key(self, k.as_str()).cloned()
+    }
+
+    fn unbind(&self, k: String) -> PatriciaTrie<T> {
+        if get_key(self, k.as_str()).is_some() { unbind_key(self, k.as_str()) } else { self.clone() }
+    }
+}
+
+impl<K: AsRef<[u8]>, T: Clone> Map<K, T> for ByteTrie<T> {
+    fn empty() -> ByteTrie<T> {
+        Tip
+    }
+
+    fn bind(&self, k: K, v: T) -> ByteTrie<T> {
+        bind_key(self, k.as_ref(), v)
+    }
+
+    fn get(&self, k: K) -> Option<T> {
+        get_key(self, k.as_ref()).cloned()
+    }
+
+    fn unbind(&self, k: K) -> ByteTrie<T> {
+        let k = k.as_ref();
+        if get_key(self, k).is_some() { unbind_key(self, k) } else { self.clone() }
+    }
+}
 
-                    let k1 = &key[i..];
-                    let k2 = &k[i..];
+// iterates over the bindings in lexicographic order of the keys, rebuilding each key from the prefixes on its path
+pub struct Iter<'a, K: 'a + ?Sized + Key, T: 'a> {
+    stack: Vec<(K::Owned, &'a Trie<K, T>)>,
+}
+
+impl<'a, K: ?Sized + Key, T> Iterator for Iter<'a, K, T> {
+    type Item = (K::Owned, &'a T);
 
-                    let children = hashmap![
-                        k1.char_at(0) => Rc::new(Node { key: k1.to_string(), value: value.clone(), children: children.clone() }),
-                        k2.char_at(0) => Rc::new(Node { key: k2.to_string(), value: Some(v), children: hashmap![] })];
+    fn next(&mut self) -> Option<(K::Owned, &'a T)> {
+        while let Some((mut k, t)) = self.stack.pop() {
+            if let Node { ref key, ref value, ref children } = *t {
+                K::push_key(&mut k, key);
+
+                for c in children.values().rev() {
+                    self.stack.push((k.clone(), c));
+                }
 
-                    Node { key: common.to_string(), value: None, children: children }
+                if let Some(ref v) = *value {
+                    return Some((k, v));
                 }
             }
         }
+
+        None
     }
+}
 
-    fn lookup(&self, k: String) -> T {
-        match *self {
-            Tip => panic!("lookup on empty tree."),
-            Node { ref key, ref value, ref children } => {
-                if k == *key {
-                    match *value {
-                        Some(ref v) => v.clone(),
-                        None => panic!("element does not exist"),
-                    }
-                } else if k.starts_with(key) {
-                    match children.get(&k.char_at(key.len())) {
-                        Some(t) => t.lookup(k[key.len()..].to_string()),
-                        None => panic!("element does not exist"),
+impl<K: ?Sized + Key, T> Trie<K, T> {
+    pub fn iter(&self) -> Iter<K, T> {
+        Iter { stack: vec![(K::empty_key(), self)] }
+    }
+
+    // iterates over the bindings whose key starts with `prefix`, in the same order as `iter`
+    pub fn iter_prefix(&self, prefix: &K) -> Iter<K, T> {
+        let mut t = self;
+        let mut k = prefix;
+        let mut path = K::empty_key();
+
+        loop {
+            match *t {
+                Tip => return Iter { stack: vec![] },
+                Node { ref key, ref children, .. } => {
+                    if key.has_prefix(k) {
+                        return Iter { stack: vec![(path, t)] };
+                    } else if k.has_prefix(key) {
+                        k = k.split_at(key.len()).1;
+                        K::push_key(&mut path, key);
+
+                        match children.get(&first_unit(k)) {
+                            Some(c) => t = c,
+                            None => return Iter { stack: vec![] }
+                        }
+                    } else {
+                        return Iter { stack: vec![] };
                     }
-                } else {
-                    panic!("element does not exist")
                 }
             }
         }
     }
+
+    // all the keys starting with `prefix`, in lexicographic order
+    pub fn keys_with_prefix(&self, prefix: &K) -> Vec<K::Owned> {
+        self.iter_prefix(prefix).map(|e| e.0).collect()
+    }
+
+    // the longest key that is a prefix of `s`, together with its value
+    pub fn longest_prefix_of(&self, s: &K) -> Option<(K::Owned, &T)> {
+        let mut t = self;
+        let mut k = s;
+        let mut path = K::empty_key();
+        let mut found = None;
+
+        while let Node { ref key, ref value, ref children } = *t {
+            if !k.has_prefix(key) {
+                break;
+            }
+
+            k = k.split_at(key.len()).1;
+            K::push_key(&mut path, key);
+
+            if let Some(ref v) = *value {
+                found = Some((path.clone(), v));
+            }
+
+            match k.first_unit().and_then(|u| children.get(&u)) {
+                Some(c) => t = c,
+                None => break
+            }
+        }
+
+        found
+    }
+}
+
+impl<'a, K: ?Sized + Key, T> IntoIterator for &'a Trie<K, T> {
+    type Item = (K::Owned, &'a T);
+    type IntoIter = Iter<'a, K, T>;
+
+    fn into_iter(self) -> Iter<'a, K, T> {
+        self.iter()
+    }
+}
+
+impl<T: Clone> FromIterator<(String, T)> for PatriciaTrie<T> {
+    fn from_iter<I: IntoIterator<Item=(String, T)>>(iter: I) -> PatriciaTrie<T> {
+        let mut t = Tip;
+        t.extend(iter);
+        t
+    }
+}
+
+impl<T: Clone> Extend<(String, T)> for PatriciaTrie<T> {
+    fn extend<I: IntoIterator<Item=(String, T)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            *self = self.bind(k, v);
+        }
+    }
 }
 
-impl<T: Display> Display for PatriciaTrie<T> {
+impl<K: AsRef<[u8]>, T: Clone> FromIterator<(K, T)> for ByteTrie<T> {
+    fn from_iter<I: IntoIterator<Item=(K, T)>>(iter: I) -> ByteTrie<T> {
+        let mut t = Tip;
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: AsRef<[u8]>, T: Clone> Extend<(K, T)> for ByteTrie<T> {
+    fn extend<I: IntoIterator<Item=(K, T)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            *self = self.bind(k, v);
+        }
+    }
+}
+
+impl<K: ?Sized + Key, T: Display> Display for Trie<K, T> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        fn aux<T: Display>(t: &PatriciaTrie<T>, mut indent: String, last: bool, f: &mut Formatter) -> Result<(), Error> {
+        // (node, indentation, whether it is the last child of its parent)
+        let mut stack = vec![(self, "".to_string(), true)];
+
+        while let Some((t, indent, last)) = stack.pop() {
             match *t {
-                Tip => writeln!(f, "()"),
+                Tip => try!(writeln!(f, "()")),
                 Node { ref key, ref value, ref children } => {
                     try!(write!(f, "{}", indent));
 
-                    if last {
+                    let indent = if last {
                         try!(write!(f, "\\-"));
-                        indent = indent + "  ";
+                        indent + "  "
                     } else {
                         try!(write!(f, "|-"));
-                        indent = indent + "| ";
-                    }
+                        indent + "| "
+                    };
 
-                    try!(write!(f, "{}", key));
+                    try!(write!(f, "{}", key.label()));
 
                     match *value {
                         Some(ref v) => try!(write!(f, " => ({})", v)),
@@ -131,16 +478,69 @@ impl<T: Display> Display for PatriciaTrie<T> {
 
                     try!(writeln!(f, ""));
 
-                    for (i, (_, c)) in children.iter().enumerate() {
-                        try!(aux(c, indent.clone(), i == children.len() - 1, f));
-                    }
+                    let cs: Vec<&Ptr<Trie<K, T>>> = children.values().collect();
 
-                    Result::Ok(())
+                    for (i, c) in cs.iter().enumerate().rev() {
+                        stack.push((c, indent.clone(), i == cs.len() - 1));
+                    }
                 }
             }
         }
 
-        aux(self, "".to_string(), true, f)
+        Result::Ok(())
+    }
+}
+
+// nodes are labelled like in `Display`, by their part of the key and their value, if any
+impl<K: ?Sized + Key, T: Display> LayoutTree for Trie<K, T> {
+    type Label = String;
+
+    fn node(&self) -> Option<(String, Vec<&Trie<K, T>>)> {
+        match *self {
+            Node { ref key, ref value, ref children } => {
+                let label = match *value {
+                    Some(ref v) => format!("{} => ({})", key.label(), v),
+                    None => key.label()
+                };
+
+                Some((label, children.values().map(|c| &**c).collect()))
+            },
+            Tip => None
+        }
+    }
+}
+
+impl<K: ?Sized + Key, T: Display> Dot for Trie<K, T> {
+    type Node = Trie<K, T>;
+
+    fn dot_roots(&self) -> Vec<&Trie<K, T>> {
+        vec![self]
+    }
+}
+
+// every node below the root has a non-empty key and is filed under its first unit, and every
+// node without a value has at least two children, since it would have been merged with its only
+// child otherwise
+#[cfg(test)]
+impl<K: ?Sized + Key, T> Trie<K, T> {
+    pub(crate) fn check_invariants(&self) {
+        let mut stack = vec![(self, true)];
+
+        while let Some((t, is_root)) = stack.pop() {
+            if let Node { ref key, ref value, ref children } = *t {
+                assert!(is_root || key.len() > 0);
+                assert!(value.is_some() || children.len() >= 2);
+
+                for (u, child) in children {
+                    match **child {
+                        Node { ref key, .. } => assert_eq!(key.first_unit(), Some(*u)),
+                        Tip => panic!("empty child")
+                    }
+
+                    stack.push((child, false));
+                }
+            }
+        }
     }
 }
 
@@ -164,4 +564,178 @@ fn patricia_trie() {
     assert_eq!(t2.lookup("te".to_string()), 5);
     assert_eq!(t2.lookup("toast".to_string()), 6);
     assert_eq!(t2.lookup("toad".to_string()), 7);
+
+    assert_eq!(t2.get("tes".to_string()), None);
+    assert_eq!(t2.get("testing".to_string()), None);
+    assert_eq!(t2.get("slowest".to_string()), None);
+    assert_eq!(t.get("test".to_string()), None);
+
+    let t3 = t2.unbind("te".to_string())
+        .unbind("slow".to_string())
+        .unbind("toad".to_string());
+
+    assert_eq!(t3.get("te".to_string()), None);
+    assert_eq!(t3.get("slow".to_string()), None);
+    assert_eq!(t3.get("toad".to_string()), None);
+    assert_eq!(t3.lookup("test".to_string()), 0);
+    assert_eq!(t3.lookup("slower".to_string()), 3);
+    assert_eq!(t3.lookup("tester".to_string()), 4);
+    assert_eq!(t3.lookup("toast".to_string()), 6);
+    assert_eq!(t2.lookup("te".to_string()), 5);
+
+    // removing "toad" leaves "to" with a single child, which is merged back into it
+    match t3 {
+        Node { ref children, .. } =>
+            match *children[&'t'] {
+                Node { ref children, .. } =>
+                    match *children[&'o'] {
+                        Node { ref key, ref value, ref children } => {
+                            assert_eq!(*key, "oast".to_string());
+                            assert_eq!(*value, Some(6));
+                            assert!(children.is_empty());
+                        },
+                        Tip => panic!("missing node")
+                    },
+                Tip => panic!("missing node")
+            },
+        Tip => panic!("missing node")
+    }
+
+    assert_eq!(t2.iter().map(|e| (e.0, *e.1)).collect::<Vec<(String, usize)>>(),
+               vec![("slow".to_string(), 1), ("slower".to_string(), 3), ("te".to_string(), 5),
+                    ("test".to_string(), 0), ("tester".to_string(), 4), ("toad".to_string(), 7),
+                    ("toast".to_string(), 6), ("water".to_string(), 2)]);
+
+    let t5: PatriciaTrie<usize> = t2.iter().map(|e| (e.0, *e.1)).collect();
+    assert_eq!(t5.iter().collect::<Vec<(String, &usize)>>(), t2.iter().collect::<Vec<(String, &usize)>>());
+
+    assert_eq!(t2.keys_with_prefix("te"), vec!["te", "test", "tester"]);
+    assert_eq!(t2.keys_with_prefix("tes"), vec!["test", "tester"]);
+    assert_eq!(t2.keys_with_prefix("to"), vec!["toad", "toast"]);
+    assert_eq!(t2.keys_with_prefix("toa"), vec!["toad", "toast"]);
+    assert_eq!(t2.keys_with_prefix("slowe"), vec!["slower"]);
+    assert_eq!(t2.keys_with_prefix("").len(), 8);
+    assert!(t2.keys_with_prefix("tx").is_empty());
+    assert!(t2.keys_with_prefix("testers").is_empty());
+    assert!(t.keys_with_prefix("").is_empty());
+
+    assert_eq!(t2.longest_prefix_of("testing"), Some(("test".to_string(), &0)));
+    assert_eq!(t2.longest_prefix_of("testers"), Some(("tester".to_string(), &4)));
+    assert_eq!(t2.longest_prefix_of("tea"), Some(("te".to_string(), &5)));
+    assert_eq!(t2.longest_prefix_of("slow"), Some(("slow".to_string(), &1)));
+    assert_eq!(t2.longest_prefix_of("toa"), None);
+    assert_eq!(t2.longest_prefix_of("t"), None);
+    assert_eq!(t.longest_prefix_of("test"), None);
+
+    assert_eq!(format!("{}", t2),
+               concat!("\\-\n",
+                       "  |-slow => (1)\n",
+                       "  | \\-er => (3)\n",
+                       "  |-t\n",
+                       "  | |-e => (5)\n",
+                       "  | | \\-st => (0)\n",
+                       "  | |   \\-er => (4)\n",
+                       "  | \\-oa\n",
+                       "  |   |-d => (7)\n",
+                       "  |   \\-st => (6)\n",
+                       "  \\-water => (2)\n"));
+
+    let t4 = t.bind("test".to_string(), 0).unbind("test".to_string());
+    assert!(match t4 { Tip => true, _ => false });
+
+    ::map::check_entry_api::<PatriciaTrie<usize>>();
+}
+
+#[test]
+fn patricia_trie_unicode() {
+    let t: PatriciaTrie<usize> = vec!["héllo", "hélicoptère", "hello", "日本", "日本語", "日曜日", "🎉", "é"]
+        .into_iter().enumerate().map(|(i, k)| (k.to_string(), i)).collect();
+
+    assert_eq!(t.lookup("héllo".to_string()), 0);
+    assert_eq!(t.lookup("hélicoptère".to_string()), 1);
+    assert_eq!(t.lookup("hello".to_string()), 2);
+    assert_eq!(t.lookup("日本".to_string()), 3);
+    assert_eq!(t.lookup("日本語".to_string()), 4);
+    assert_eq!(t.lookup("日曜日".to_string()), 5);
+    assert_eq!(t.lookup("🎉".to_string()), 6);
+    assert_eq!(t.lookup("é".to_string()), 7);
+
+    assert_eq!(t.get("日".to_string()), None);
+    assert_eq!(t.get("hél".to_string()), None);
+    assert_eq!(t.get("🎉🎉".to_string()), None);
+
+    assert_eq!(t.iter().map(|e| e.0).collect::<Vec<String>>(),
+               vec!["hello", "hélicoptère", "héllo", "é", "日曜日", "日本", "日本語", "🎉"]);
+
+    assert_eq!(t.keys_with_prefix("hé"), vec!["hélicoptère", "héllo"]);
+    assert_eq!(t.keys_with_prefix("日本"), vec!["日本", "日本語"]);
+    assert_eq!(t.longest_prefix_of("日本語です"), Some(("日本語".to_string(), &4)));
+    assert_eq!(t.longest_prefix_of("éa"), Some(("é".to_string(), &7)));
+
+    let t2 = t.unbind("日本".to_string()).unbind("héllo".to_string());
+    assert_eq!(t2.get("日本".to_string()), None);
+    assert_eq!(t2.lookup("日本語".to_string()), 4);
+    assert_eq!(t2.lookup("hélicoptère".to_string()), 1);
+    assert_eq!(t2.iter().count(), 6);
+}
+
+#[test]
+fn byte_trie() {
+    use std::collections::BTreeMap;
+
+    use model::Rng;
+
+    let t: ByteTrie<usize> = Tip;
+    let t2 = t.bind(b"test", 0)
+        .bind(b"slow", 1)
+        .bind(b"water", 2)
+        .bind(b"slower", 3)
+        .bind(b"tester", 4)
+        .bind(b"te", 5)
+        .bind(vec![0xff, 0x00], 6)
+        .bind(vec![], 7);
+
+    assert_eq!(t2.lookup(b"test"), 0);
+    assert_eq!(t2.lookup("slow"), 1);
+    assert_eq!(t2.lookup(b"tester".to_vec()), 4);
+    assert_eq!(t2.lookup(&[0xff, 0x00][..]), 6);
+    assert_eq!(t2.lookup(b""), 7);
+    assert_eq!(t2.get(b"tes"), None);
+    assert_eq!(t2.get(&[0xff][..]), None);
+    assert_eq!(t.get(b"test"), None);
+
+    assert_eq!(t2.keys_with_prefix(b"te"), vec![b"te".to_vec(), b"test".to_vec(), b"tester".to_vec()]);
+    assert_eq!(t2.longest_prefix_of(b"slowest"), Some((b"slow".to_vec(), &1)));
+    assert_eq!(t2.longest_prefix_of(b"x"), Some((vec![], &7)));
+
+    let t3 = t2.unbind(b"te").unbind(b"").unbind(b"missing");
+    assert_eq!(t3.get(b"te"), None);
+    assert_eq!(t3.get(b""), None);
+    assert_eq!(t3.lookup(b"tester"), 4);
+
+    ::map::check_entry_api::<ByteTrie<usize>>();
+
+    let mut rng = Rng(362436069);
+
+    // short keys over a small alphabet share a lot of prefixes
+    let mut t: ByteTrie<u32> = Tip;
+    let mut model: BTreeMap<Vec<u8>, u32> = BTreeMap::new();
+
+    for _ in 0..3000 {
+        let r = rng.next();
+        let k: Vec<u8> = (0..r % 6).map(|_| (rng.next() % 3) as u8).collect();
+
+        if r % 3 == 0 {
+            t = t.unbind(&k);
+            model.remove(&k);
+        } else {
+            t = t.bind(&k, r);
+            model.insert(k.clone(), r);
+        }
+
+        assert_eq!(t.get(&k), model.get(&k).cloned());
+    }
+
+    assert_eq!(t.iter().map(|e| (e.0, *e.1)).collect::<Vec<(Vec<u8>, u32)>>(),
+               model.into_iter().collect::<Vec<(Vec<u8>, u32)>>());
 }
@@ -0,0 +1,49 @@
+// Checks that the operations which leave a structure unchanged don't allocate, by counting the
+// allocations made by the current thread while they run.
+
+extern crate okasaki;
+
+use okasaki::set::Set;
+use okasaki::tree::Tree;
+
+mod counting;
+
+fn allocations<R, F: FnOnce() -> R>(f: F) -> (R, usize) {
+    let start = counting::allocations();
+    let r = f();
+    (r, counting::allocations() - start)
+}
+
+#[test]
+fn tree_insert_duplicate() {
+    let t: Tree<u32> = (0..1000).map(|x| x * 7919 % 1000).collect();
+
+    for x in vec![0, 1, 500, 999] {
+        let (t2, n) = allocations(|| t.insert(x));
+        assert_eq!(n, 0);
+        assert_eq!(t2, t);
+    }
+
+    let (t2, n) = allocations(|| t.insert(1000));
+    assert!(n > 0);
+    assert!(t2.member(1000));
+}
+
+// the subtrees are shared with the original tree, so the only allocation is the copy of its root
+// element, whatever the depth of the duplicate
+#[test]
+fn tree_insert_duplicate_string() {
+    let t: Tree<String> = (0..1000).map(|x| format!("{:03}", x * 7919 % 1000)).collect();
+    let root = match t {
+        Tree::Node(_, ref x, _) => x.clone(),
+        Tree::Tip => unreachable!()
+    };
+
+    for x in vec!["000", "001", "500", "999"] {
+        let x = x.to_string();
+        let (_, copy) = allocations(|| root.clone());
+        let (t2, n) = allocations(|| t.insert(x));
+        assert_eq!(n, copy);
+        assert_eq!(t2, t);
+    }
+}